@@ -193,3 +193,42 @@ b2RayCastCallback* RayCastCallbackLink_as_base(RayCastCallbackLink* self) {
 void RayCastCallbackLink_drop(RayCastCallbackLink* self) {
     delete self;
 }
+
+typedef f32 (*RCCReportChildCB)(RustObject, b2Fixture*, i32,
+                                const b2Vec2*, const b2Vec2*, f32);
+
+// Like the wrapper of b2World::RayCast, but b2RayCastCallback does not
+// tell which child of the fixture was hit.
+struct ChildRayCastWrapper {
+    f32 RayCastCallback(const b2RayCastInput& input, i32 proxy_id) {
+        b2FixtureProxy* proxy = (b2FixtureProxy*)broad_phase->GetUserData(proxy_id);
+        b2Fixture* fixture = proxy->fixture;
+        i32 index = proxy->childIndex;
+        b2RayCastOutput output;
+        if (fixture->RayCast(&output, input, index)) {
+            f32 fraction = output.fraction;
+            b2Vec2 point = (1.0f - fraction) * input.p1 + fraction * input.p2;
+            return report_child(object, fixture, index, &point, &output.normal, fraction);
+        }
+        return input.maxFraction;
+    }
+
+    const b2BroadPhase* broad_phase;
+    RustObject object;
+    RCCReportChildCB report_child;
+};
+
+void World_ray_cast_children(const b2World* self,
+                             RustObject object,
+                             RCCReportChildCB rc,
+                             const b2Vec2* p1, const b2Vec2* p2) {
+    ChildRayCastWrapper wrapper;
+    wrapper.broad_phase = &self->GetContactManager().m_broadPhase;
+    wrapper.object = object;
+    wrapper.report_child = rc;
+    b2RayCastInput input;
+    input.maxFraction = 1.0f;
+    input.p1 = *p1;
+    input.p2 = *p2;
+    wrapper.broad_phase->RayCast(&wrapper, input);
+}
@@ -0,0 +1,433 @@
+// Internal state that the public Box2D API does not expose,
+// needed to rebuild a world that steps exactly like the original.
+
+struct WorldExactState {
+    i32 flags;
+    f32 inv_dt0;
+    bool step_complete;
+};
+
+void World_get_exact_state(const b2World* self, WorldExactState* state) {
+    state->flags = b2World_m_flags(self);
+    state->inv_dt0 = b2World_m_inv_dt0(self);
+    state->step_complete = b2World_m_stepComplete(self);
+}
+void World_set_exact_state(b2World* self, const WorldExactState* state) {
+    b2World_m_flags(self) = state->flags;
+    b2World_m_inv_dt0(self) = state->inv_dt0;
+    b2World_m_stepComplete(self) = state->step_complete;
+}
+
+struct BodyExactState {
+    b2Vec2 position;
+    f32 rotation[2];
+    b2Vec2 local_center;
+    b2Vec2 c0;
+    b2Vec2 c;
+    f32 a0;
+    f32 a;
+    f32 alpha0;
+    b2Vec2 linear_velocity;
+    f32 angular_velocity;
+    b2Vec2 force;
+    f32 torque;
+    f32 mass;
+    f32 inv_mass;
+    f32 inertia;
+    f32 inv_inertia;
+    f32 sleep_time;
+    u16 flags;
+};
+
+void Body_get_exact_state(const b2Body* self, BodyExactState* state) {
+    const b2Transform& xf = b2Body_m_xf(self);
+    const b2Sweep& sweep = b2Body_m_sweep(self);
+    state->position = xf.p;
+    state->rotation[0] = xf.q.s;
+    state->rotation[1] = xf.q.c;
+    state->local_center = sweep.localCenter;
+    state->c0 = sweep.c0;
+    state->c = sweep.c;
+    state->a0 = sweep.a0;
+    state->a = sweep.a;
+    state->alpha0 = sweep.alpha0;
+    state->linear_velocity = b2Body_m_linearVelocity(self);
+    state->angular_velocity = b2Body_m_angularVelocity(self);
+    state->force = b2Body_m_force(self);
+    state->torque = b2Body_m_torque(self);
+    state->mass = b2Body_m_mass(self);
+    state->inv_mass = b2Body_m_invMass(self);
+    state->inertia = b2Body_m_I(self);
+    state->inv_inertia = b2Body_m_invI(self);
+    state->sleep_time = b2Body_m_sleepTime(self);
+    state->flags = b2Body_m_flags(self);
+}
+void Body_set_exact_state(b2Body* self, const BodyExactState* state) {
+    b2Transform& xf = b2Body_m_xf(self);
+    b2Sweep& sweep = b2Body_m_sweep(self);
+    xf.p = state->position;
+    xf.q.s = state->rotation[0];
+    xf.q.c = state->rotation[1];
+    sweep.localCenter = state->local_center;
+    sweep.c0 = state->c0;
+    sweep.c = state->c;
+    sweep.a0 = state->a0;
+    sweep.a = state->a;
+    sweep.alpha0 = state->alpha0;
+    b2Body_m_linearVelocity(self) = state->linear_velocity;
+    b2Body_m_angularVelocity(self) = state->angular_velocity;
+    b2Body_m_force(self) = state->force;
+    b2Body_m_torque(self) = state->torque;
+    b2Body_m_mass(self) = state->mass;
+    b2Body_m_invMass(self) = state->inv_mass;
+    b2Body_m_I(self) = state->inertia;
+    b2Body_m_invI(self) = state->inv_inertia;
+    b2Body_m_sleepTime(self) = state->sleep_time;
+    b2Body_m_flags(self) = state->flags;
+}
+
+struct JointExactState {
+    f32 impulse[4];
+    i32 limit_state;
+};
+
+// Points to the warm starting impulses of the joint, returns their count.
+static i32 Joint_exact_fields(b2Joint* self,
+                              f32* fields[4],
+                              b2LimitState** limit_state) {
+    *limit_state = NULL;
+    switch (self->GetType()) {
+        case e_revoluteJoint: {
+            b2RevoluteJoint* j = static_cast<b2RevoluteJoint*>(self);
+            b2Vec3& impulse = b2RevoluteJoint_m_impulse(j);
+            fields[0] = &impulse.x;
+            fields[1] = &impulse.y;
+            fields[2] = &impulse.z;
+            fields[3] = &b2RevoluteJoint_m_motorImpulse(j);
+            *limit_state = &b2RevoluteJoint_m_limitState(j);
+            return 4;
+        }
+        case e_prismaticJoint: {
+            b2PrismaticJoint* j = static_cast<b2PrismaticJoint*>(self);
+            b2Vec3& impulse = b2PrismaticJoint_m_impulse(j);
+            fields[0] = &impulse.x;
+            fields[1] = &impulse.y;
+            fields[2] = &impulse.z;
+            fields[3] = &b2PrismaticJoint_m_motorImpulse(j);
+            *limit_state = &b2PrismaticJoint_m_limitState(j);
+            return 4;
+        }
+        case e_distanceJoint:
+            fields[0] = &b2DistanceJoint_m_impulse(static_cast<b2DistanceJoint*>(self));
+            return 1;
+        case e_pulleyJoint:
+            fields[0] = &b2PulleyJoint_m_impulse(static_cast<b2PulleyJoint*>(self));
+            return 1;
+        case e_mouseJoint: {
+            b2Vec2& impulse = b2MouseJoint_m_impulse(static_cast<b2MouseJoint*>(self));
+            fields[0] = &impulse.x;
+            fields[1] = &impulse.y;
+            return 2;
+        }
+        case e_gearJoint:
+            fields[0] = &b2GearJoint_m_impulse(static_cast<b2GearJoint*>(self));
+            return 1;
+        case e_wheelJoint: {
+            b2WheelJoint* j = static_cast<b2WheelJoint*>(self);
+            fields[0] = &b2WheelJoint_m_impulse(j);
+            fields[1] = &b2WheelJoint_m_motorImpulse(j);
+            fields[2] = &b2WheelJoint_m_springImpulse(j);
+            return 3;
+        }
+        case e_weldJoint: {
+            b2Vec3& impulse = b2WeldJoint_m_impulse(static_cast<b2WeldJoint*>(self));
+            fields[0] = &impulse.x;
+            fields[1] = &impulse.y;
+            fields[2] = &impulse.z;
+            return 3;
+        }
+        case e_frictionJoint: {
+            b2FrictionJoint* j = static_cast<b2FrictionJoint*>(self);
+            b2Vec2& impulse = b2FrictionJoint_m_linearImpulse(j);
+            fields[0] = &impulse.x;
+            fields[1] = &impulse.y;
+            fields[2] = &b2FrictionJoint_m_angularImpulse(j);
+            return 3;
+        }
+        case e_ropeJoint: {
+            b2RopeJoint* j = static_cast<b2RopeJoint*>(self);
+            fields[0] = &b2RopeJoint_m_impulse(j);
+            *limit_state = &b2RopeJoint_m_state(j);
+            return 1;
+        }
+        case e_motorJoint: {
+            b2MotorJoint* j = static_cast<b2MotorJoint*>(self);
+            b2Vec2& impulse = b2MotorJoint_m_linearImpulse(j);
+            fields[0] = &impulse.x;
+            fields[1] = &impulse.y;
+            fields[2] = &b2MotorJoint_m_angularImpulse(j);
+            return 3;
+        }
+        default:
+            return 0;
+    }
+}
+
+void Joint_get_exact_state(const b2Joint* self, JointExactState* state) {
+    f32* fields[4];
+    b2LimitState* limit_state;
+    i32 count = Joint_exact_fields(const_cast<b2Joint*>(self), fields, &limit_state);
+    for (i32 i = 0; i < 4; ++i) {
+        state->impulse[i] = i < count ? *fields[i] : 0.0f;
+    }
+    state->limit_state = limit_state ? *limit_state : e_inactiveLimit;
+}
+void Joint_set_exact_state(b2Joint* self, const JointExactState* state) {
+    f32* fields[4];
+    b2LimitState* limit_state;
+    i32 count = Joint_exact_fields(self, fields, &limit_state);
+    for (i32 i = 0; i < count; ++i) {
+        *fields[i] = state->impulse[i];
+    }
+    if (limit_state) {
+        *limit_state = (b2LimitState)state->limit_state;
+    }
+}
+
+struct ManifoldPointExactState {
+    b2Vec2 local_point;
+    f32 normal_impulse;
+    f32 tangent_impulse;
+    u32 id;
+};
+
+struct ContactExactState {
+    ManifoldPointExactState points[b2_maxManifoldPoints];
+    b2Vec2 local_normal;
+    b2Vec2 local_point;
+    i32 manifold_type;
+    i32 point_count;
+    u32 flags;
+    i32 toi_count;
+    f32 toi;
+    f32 friction;
+    f32 restitution;
+    f32 tangent_speed;
+};
+
+void Contact_get_exact_state(const b2Contact* self, ContactExactState* state) {
+    const b2Manifold& m = *self->GetManifold();
+    for (i32 i = 0; i < b2_maxManifoldPoints; ++i) {
+        state->points[i].local_point = m.points[i].localPoint;
+        state->points[i].normal_impulse = m.points[i].normalImpulse;
+        state->points[i].tangent_impulse = m.points[i].tangentImpulse;
+        state->points[i].id = m.points[i].id.key;
+    }
+    state->local_normal = m.localNormal;
+    state->local_point = m.localPoint;
+    state->manifold_type = m.type;
+    state->point_count = m.pointCount;
+    state->flags = b2Contact_m_flags(self);
+    state->toi_count = b2Contact_m_toiCount(self);
+    state->toi = b2Contact_m_toi(self);
+    state->friction = self->GetFriction();
+    state->restitution = self->GetRestitution();
+    state->tangent_speed = self->GetTangentSpeed();
+}
+void Contact_set_exact_state(b2Contact* self, const ContactExactState* state) {
+    b2Manifold& m = *self->GetManifold();
+    for (i32 i = 0; i < b2_maxManifoldPoints; ++i) {
+        m.points[i].localPoint = state->points[i].local_point;
+        m.points[i].normalImpulse = state->points[i].normal_impulse;
+        m.points[i].tangentImpulse = state->points[i].tangent_impulse;
+        m.points[i].id.key = state->points[i].id;
+    }
+    m.localNormal = state->local_normal;
+    m.localPoint = state->local_point;
+    m.type = (b2Manifold::Type)state->manifold_type;
+    m.pointCount = state->point_count;
+    b2Contact_m_flags(self) = state->flags;
+    b2Contact_m_toiCount(self) = state->toi_count;
+    b2Contact_m_toi(self) = state->toi;
+    self->SetFriction(state->friction);
+    self->SetRestitution(state->restitution);
+    self->SetTangentSpeed(state->tangent_speed);
+}
+
+void World_clear_contacts(b2World* self) {
+    b2ContactManager& manager = b2World_m_contactManager(self);
+    b2Contact* c = manager.m_contactList;
+    while (c != NULL) {
+        b2Contact* next = c->GetNext();
+        // touching contacts wake their bodies when destroyed
+        c->GetManifold()->pointCount = 0;
+        b2Contact_Destroy(c, manager.m_allocator);
+        c = next;
+    }
+    manager.m_contactList = NULL;
+    manager.m_contactCount = 0;
+
+    for (b2Body* b = self->GetBodyList(); b != NULL; b = b->GetNext()) {
+        b2Body_m_contactList(b) = NULL;
+    }
+}
+
+// Like b2ContactManager::AddPair, without filtering nor waking the bodies.
+b2Contact* World_create_exact_contact(b2World* self,
+                                      b2Fixture* fixture_a,
+                                      i32 index_a,
+                                      b2Fixture* fixture_b,
+                                      i32 index_b) {
+    b2ContactManager& manager = b2World_m_contactManager(self);
+    b2Contact* c = b2Contact_Create(fixture_a, index_a,
+                                    fixture_b, index_b,
+                                    manager.m_allocator);
+    if (c == NULL) {
+        return NULL;
+    }
+
+    // the contact may have swapped the fixtures
+    b2Body* body_a = c->GetFixtureA()->GetBody();
+    b2Body* body_b = c->GetFixtureB()->GetBody();
+
+    b2Contact_m_prev(c) = NULL;
+    b2Contact_m_next(c) = manager.m_contactList;
+    if (manager.m_contactList != NULL) {
+        b2Contact_m_prev(manager.m_contactList) = c;
+    }
+    manager.m_contactList = c;
+
+    b2ContactEdge& node_a = b2Contact_m_nodeA(c);
+    b2ContactEdge*& list_a = b2Body_m_contactList(body_a);
+    node_a.contact = c;
+    node_a.other = body_b;
+    node_a.prev = NULL;
+    node_a.next = list_a;
+    if (list_a != NULL) {
+        list_a->prev = &node_a;
+    }
+    list_a = &node_a;
+
+    b2ContactEdge& node_b = b2Contact_m_nodeB(c);
+    b2ContactEdge*& list_b = b2Body_m_contactList(body_b);
+    node_b.contact = c;
+    node_b.other = body_a;
+    node_b.prev = NULL;
+    node_b.next = list_b;
+    if (list_b != NULL) {
+        list_b->prev = &node_b;
+    }
+    list_b = &node_b;
+
+    ++manager.m_contactCount;
+    return c;
+}
+
+struct ProxyTreeInfo {
+    i32 root;
+    i32 node_count;
+    i32 node_capacity;
+    i32 free_list;
+    u32 path;
+    i32 insertion_count;
+    i32 proxy_count;
+    i32 move_count;
+};
+
+struct ProxyTreeNode {
+    b2AABB aabb;
+    // null unless the node is a leaf
+    const b2Fixture* fixture;
+    i32 child_index;
+    i32 parent_or_next;
+    i32 child1;
+    i32 child2;
+    i32 height;
+};
+
+i32 Fixture_get_proxy_count(const b2Fixture* self) {
+    return b2Fixture_m_proxyCount(self);
+}
+
+void World_get_proxy_tree_info(const b2World* self, ProxyTreeInfo* info) {
+    const b2BroadPhase& broad_phase = b2World_m_contactManager(self).m_broadPhase;
+    const b2DynamicTree& tree = b2BroadPhase_m_tree(&broad_phase);
+    info->root = b2DynamicTree_m_root(&tree);
+    info->node_count = b2DynamicTree_m_nodeCount(&tree);
+    info->node_capacity = b2DynamicTree_m_nodeCapacity(&tree);
+    info->free_list = b2DynamicTree_m_freeList(&tree);
+    info->path = b2DynamicTree_m_path(&tree);
+    info->insertion_count = b2DynamicTree_m_insertionCount(&tree);
+    info->proxy_count = b2BroadPhase_m_proxyCount(&broad_phase);
+    info->move_count = b2BroadPhase_m_moveCount(&broad_phase);
+}
+
+void World_get_proxy_tree(const b2World* self, ProxyTreeNode* nodes, i32* moves) {
+    const b2BroadPhase& broad_phase = b2World_m_contactManager(self).m_broadPhase;
+    const b2DynamicTree& tree = b2BroadPhase_m_tree(&broad_phase);
+    const b2TreeNode* tree_nodes = b2DynamicTree_m_nodes(&tree);
+    for (i32 i = 0; i < b2DynamicTree_m_nodeCapacity(&tree); ++i) {
+        const b2TreeNode& node = tree_nodes[i];
+        nodes[i].aabb = node.aabb;
+        nodes[i].fixture = NULL;
+        nodes[i].child_index = 0;
+        if (node.height >= 0 && node.IsLeaf()) {
+            const b2FixtureProxy* proxy = (const b2FixtureProxy*)node.userData;
+            nodes[i].fixture = proxy->fixture;
+            nodes[i].child_index = proxy->childIndex;
+        }
+        nodes[i].parent_or_next = node.parent;
+        nodes[i].child1 = node.child1;
+        nodes[i].child2 = node.child2;
+        nodes[i].height = node.height;
+    }
+    memcpy(moves,
+           b2BroadPhase_m_moveBuffer(&broad_phase),
+           b2BroadPhase_m_moveCount(&broad_phase) * sizeof(i32));
+}
+
+// Replaces the whole tree, the proxies of the fixtures get the ids of their leaves.
+void World_set_proxy_tree(b2World* self,
+                          const ProxyTreeInfo* info,
+                          const ProxyTreeNode* nodes,
+                          const i32* moves) {
+    b2BroadPhase& broad_phase = b2World_m_contactManager(self).m_broadPhase;
+    b2DynamicTree& tree = b2BroadPhase_m_tree(&broad_phase);
+
+    b2TreeNode*& tree_nodes = b2DynamicTree_m_nodes(&tree);
+    b2Free(tree_nodes);
+    tree_nodes = (b2TreeNode*)b2Alloc(info->node_capacity * sizeof(b2TreeNode));
+    memset(tree_nodes, 0, info->node_capacity * sizeof(b2TreeNode));
+    for (i32 i = 0; i < info->node_capacity; ++i) {
+        b2TreeNode& node = tree_nodes[i];
+        node.aabb = nodes[i].aabb;
+        node.userData = NULL;
+        if (nodes[i].fixture != NULL) {
+            b2Fixture* fixture = const_cast<b2Fixture*>(nodes[i].fixture);
+            b2FixtureProxy* proxy = b2Fixture_m_proxies(fixture) + nodes[i].child_index;
+            proxy->proxyId = i;
+            node.userData = proxy;
+        }
+        node.parent = nodes[i].parent_or_next;
+        node.child1 = nodes[i].child1;
+        node.child2 = nodes[i].child2;
+        node.height = nodes[i].height;
+    }
+    b2DynamicTree_m_root(&tree) = info->root;
+    b2DynamicTree_m_nodeCount(&tree) = info->node_count;
+    b2DynamicTree_m_nodeCapacity(&tree) = info->node_capacity;
+    b2DynamicTree_m_freeList(&tree) = info->free_list;
+    b2DynamicTree_m_path(&tree) = info->path;
+    b2DynamicTree_m_insertionCount(&tree) = info->insertion_count;
+
+    b2BroadPhase_m_proxyCount(&broad_phase) = info->proxy_count;
+    i32*& move_buffer = b2BroadPhase_m_moveBuffer(&broad_phase);
+    i32& move_capacity = b2BroadPhase_m_moveCapacity(&broad_phase);
+    if (info->move_count > move_capacity) {
+        b2Free(move_buffer);
+        move_capacity = info->move_count;
+        move_buffer = (i32*)b2Alloc(info->move_count * sizeof(i32));
+    }
+    memcpy(move_buffer, moves, info->move_count * sizeof(i32));
+    b2BroadPhase_m_moveCount(&broad_phase) = info->move_count;
+}
@@ -0,0 +1,120 @@
+// Accessors for the internal state of Box2D used by the exact snapshots.
+//
+// Access checking does not apply to the names in an explicit template
+// instantiation, so a member pointer can be taken there and handed out
+// through a friend function of a tag type. Unlike redefining the access
+// keywords this keeps the Box2D headers untouched, and a renamed member
+// fails to compile instead of silently changing the layout.
+
+template <typename Tag, typename Tag::type Member>
+struct ExactAccess {
+    friend typename Tag::type exact_access(Tag) {
+        return Member;
+    }
+};
+
+// Defines `Class_name(self)`, returning a reference to `self->name`.
+#define EXACT_MEMBER(Class, Type, name)                                  \
+    struct Class##_##name##_tag {                                        \
+        typedef Type Class::*type;                                       \
+        friend type exact_access(Class##_##name##_tag);                  \
+    };                                                                   \
+    template struct ExactAccess<Class##_##name##_tag, &Class::name>;     \
+    static inline Type& Class##_##name(Class* self) {                    \
+        return self->*exact_access(Class##_##name##_tag());              \
+    }                                                                    \
+    static inline Type const& Class##_##name(const Class* self) {        \
+        return self->*exact_access(Class##_##name##_tag());              \
+    }
+
+// Defines a tag for the static member function `Class::name`.
+#define EXACT_STATIC(Class, Type, name)                                  \
+    struct Class##_##name##_tag {                                        \
+        typedef Type* type;                                              \
+        friend type exact_access(Class##_##name##_tag);                  \
+    };                                                                   \
+    template struct ExactAccess<Class##_##name##_tag, &Class::name>;
+
+EXACT_MEMBER(b2World, int32, m_flags)
+EXACT_MEMBER(b2World, float32, m_inv_dt0)
+EXACT_MEMBER(b2World, bool, m_stepComplete)
+EXACT_MEMBER(b2World, b2ContactManager, m_contactManager)
+
+EXACT_MEMBER(b2Body, b2Transform, m_xf)
+EXACT_MEMBER(b2Body, b2Sweep, m_sweep)
+EXACT_MEMBER(b2Body, b2Vec2, m_linearVelocity)
+EXACT_MEMBER(b2Body, float32, m_angularVelocity)
+EXACT_MEMBER(b2Body, b2Vec2, m_force)
+EXACT_MEMBER(b2Body, float32, m_torque)
+EXACT_MEMBER(b2Body, float32, m_mass)
+EXACT_MEMBER(b2Body, float32, m_invMass)
+EXACT_MEMBER(b2Body, float32, m_I)
+EXACT_MEMBER(b2Body, float32, m_invI)
+EXACT_MEMBER(b2Body, float32, m_sleepTime)
+EXACT_MEMBER(b2Body, uint16, m_flags)
+EXACT_MEMBER(b2Body, b2ContactEdge*, m_contactList)
+
+EXACT_MEMBER(b2Fixture, b2FixtureProxy*, m_proxies)
+EXACT_MEMBER(b2Fixture, int32, m_proxyCount)
+
+EXACT_MEMBER(b2RevoluteJoint, b2Vec3, m_impulse)
+EXACT_MEMBER(b2RevoluteJoint, float32, m_motorImpulse)
+EXACT_MEMBER(b2RevoluteJoint, b2LimitState, m_limitState)
+EXACT_MEMBER(b2PrismaticJoint, b2Vec3, m_impulse)
+EXACT_MEMBER(b2PrismaticJoint, float32, m_motorImpulse)
+EXACT_MEMBER(b2PrismaticJoint, b2LimitState, m_limitState)
+EXACT_MEMBER(b2DistanceJoint, float32, m_impulse)
+EXACT_MEMBER(b2PulleyJoint, float32, m_impulse)
+EXACT_MEMBER(b2MouseJoint, b2Vec2, m_impulse)
+EXACT_MEMBER(b2GearJoint, float32, m_impulse)
+EXACT_MEMBER(b2WheelJoint, float32, m_impulse)
+EXACT_MEMBER(b2WheelJoint, float32, m_motorImpulse)
+EXACT_MEMBER(b2WheelJoint, float32, m_springImpulse)
+EXACT_MEMBER(b2WeldJoint, b2Vec3, m_impulse)
+EXACT_MEMBER(b2FrictionJoint, b2Vec2, m_linearImpulse)
+EXACT_MEMBER(b2FrictionJoint, float32, m_angularImpulse)
+EXACT_MEMBER(b2RopeJoint, float32, m_impulse)
+EXACT_MEMBER(b2RopeJoint, b2LimitState, m_state)
+EXACT_MEMBER(b2MotorJoint, b2Vec2, m_linearImpulse)
+EXACT_MEMBER(b2MotorJoint, float32, m_angularImpulse)
+
+EXACT_MEMBER(b2Contact, uint32, m_flags)
+EXACT_MEMBER(b2Contact, b2Contact*, m_prev)
+EXACT_MEMBER(b2Contact, b2Contact*, m_next)
+EXACT_MEMBER(b2Contact, b2ContactEdge, m_nodeA)
+EXACT_MEMBER(b2Contact, b2ContactEdge, m_nodeB)
+EXACT_MEMBER(b2Contact, int32, m_toiCount)
+EXACT_MEMBER(b2Contact, float32, m_toi)
+EXACT_STATIC(b2Contact, b2ContactCreateFcn, Create)
+EXACT_STATIC(b2Contact, b2ContactDestroyFcn, Destroy)
+
+EXACT_MEMBER(b2BroadPhase, b2DynamicTree, m_tree)
+EXACT_MEMBER(b2BroadPhase, int32, m_proxyCount)
+EXACT_MEMBER(b2BroadPhase, int32*, m_moveBuffer)
+EXACT_MEMBER(b2BroadPhase, int32, m_moveCapacity)
+EXACT_MEMBER(b2BroadPhase, int32, m_moveCount)
+
+EXACT_MEMBER(b2DynamicTree, int32, m_root)
+EXACT_MEMBER(b2DynamicTree, b2TreeNode*, m_nodes)
+EXACT_MEMBER(b2DynamicTree, int32, m_nodeCount)
+EXACT_MEMBER(b2DynamicTree, int32, m_nodeCapacity)
+EXACT_MEMBER(b2DynamicTree, int32, m_freeList)
+EXACT_MEMBER(b2DynamicTree, uint32, m_path)
+EXACT_MEMBER(b2DynamicTree, int32, m_insertionCount)
+
+#undef EXACT_MEMBER
+#undef EXACT_STATIC
+
+static inline b2Contact* b2Contact_Create(b2Fixture* fixture_a,
+                                          int32 index_a,
+                                          b2Fixture* fixture_b,
+                                          int32 index_b,
+                                          b2BlockAllocator* allocator) {
+    return exact_access(b2Contact_Create_tag())(fixture_a, index_a,
+                                                fixture_b, index_b,
+                                                allocator);
+}
+
+static inline void b2Contact_Destroy(b2Contact* contact, b2BlockAllocator* allocator) {
+    exact_access(b2Contact_Destroy_tag())(contact, allocator);
+}
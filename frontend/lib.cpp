@@ -1,5 +1,9 @@
 #include <Box2D/Box2D.h>
 #include <stdint.h>
+#include <string.h>
+
+// templates can not have C linkage
+#include "dynamics/world_exact_access.cpp"
 
 extern "C" {
 
@@ -25,6 +29,7 @@ struct RustFatObject {
 #include "dynamics/fixture.cpp"
 #include "dynamics/world.cpp"
 #include "dynamics/world_callbacks.cpp"
+#include "dynamics/world_exact.cpp"
 
 #include "collision/collision.cpp"
 #include "collision/shapes/shape.cpp"
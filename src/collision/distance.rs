@@ -1,9 +1,11 @@
 use std::mem;
+use std::ptr;
 use std::marker::PhantomData;
 use common::math::{Vec2, Transform};
 use collision::shapes::Shape;
 
 #[repr(C)]
+#[derive(Clone)]
 #[doc(hidden)]
 pub struct RawProxy {
     buffer: [Vec2; 2],
@@ -14,10 +16,22 @@ pub struct RawProxy {
 
 impl RawProxy {
     unsafe fn new(shape: *const ffi::Shape, index: i32) -> RawProxy {
-        let mut proxy = mem::zeroed();
+        let mut proxy: RawProxy = mem::zeroed();
         ffi::DistanceProxy_set(&mut proxy, shape, index);
+        // chain children are copied into the proxy's own buffer,
+        // which moves along with it
+        if proxy.vertices == proxy.buffer.as_ptr() {
+            proxy.vertices = ptr::null();
+        }
         proxy
     }
+
+    #[doc(hidden)]
+    pub fn rebase(&mut self) {
+        if self.vertices.is_null() {
+            self.vertices = self.buffer.as_ptr();
+        }
+    }
 }
 
 pub struct Proxy<'a> {
@@ -45,6 +59,7 @@ pub struct SimplexCache {
 }
 
 #[repr(C)]
+#[derive(Clone)]
 #[doc(hidden)]
 pub struct RawInput {
     proxy_a: RawProxy,
@@ -80,8 +95,11 @@ impl<'a> Input<'a> {
 
     pub fn query(&self, cache: &mut SimplexCache) -> Output {
         unsafe {
+            let mut raw = self.raw.clone();
+            raw.proxy_a.rebase();
+            raw.proxy_b.rebase();
             let mut out = mem::zeroed();
-            ffi::distance(&mut out, cache, &self.raw);
+            ffi::distance(&mut out, cache, &raw);
             out
         }
     }
@@ -136,6 +136,19 @@ impl AABB {
             upper: Vec2 { x: 0., y: 0. },
         }
     }
+
+    pub fn combine(&self, other: &AABB) -> AABB {
+        AABB {
+            lower: Vec2 {
+                x: self.lower.x.min(other.lower.x),
+                y: self.lower.y.min(other.lower.y),
+            },
+            upper: Vec2 {
+                x: self.upper.x.max(other.upper.x),
+                y: self.upper.y.max(other.upper.y),
+            },
+        }
+    }
 }
 
 pub fn test_overlap<A, B>(shape_a: &A,
@@ -3,8 +3,9 @@ use std::ptr;
 use std::slice;
 
 use wrap::*;
-use common::math::Vec2;
-use super::{Shape, EdgeShape};
+use common::math::{Vec2, Transform};
+use common::settings::LINEAR_SLOP;
+use super::{Shape, EdgeShape, ShapeError, check_finite};
 
 wrap_shape! {
     ffi::ChainShape => ChainShape
@@ -29,6 +30,66 @@ impl ChainShape {
         s
     }
 
+    /// Checks the vertices the way `b2ChainShape::CreateLoop` asserts on them.
+    pub fn try_new_loop(vertices: &[Vec2]) -> Result<Self, ShapeError> {
+        check_vertices(vertices, 3, true)?;
+        Ok(Self::new_loop(vertices))
+    }
+
+    /// Checks the vertices the way `b2ChainShape::CreateChain` asserts on them.
+    pub fn try_new_chain(vertices: &[Vec2]) -> Result<Self, ShapeError> {
+        check_vertices(vertices, 2, false)?;
+        Ok(Self::new_chain(vertices))
+    }
+
+    /// Box2D keeps no loop flag, a loop is recognized by the state
+    /// `create_loop` leaves: the first vertex repeated at the end and the
+    /// vertices around it as ghost vertices.
+    pub fn is_loop(&self) -> bool {
+        let vertices = self.vertices();
+        let n = vertices.len();
+        n > 3 && vertices[0] == vertices[n - 1] &&
+        self.prev_vertex() == Some(vertices[n - 2]) && self.next_vertex() == Some(vertices[1])
+    }
+
+    pub fn transformed(&self, xf: &Transform) -> Self {
+        let f = |v| xf * v;
+        let (vertices, is_loop) = self.map_vertices(&f);
+        if is_loop {
+            Self::new_loop(&vertices)
+        } else {
+            self.map_ghosts(Self::new_chain(&vertices), &f)
+        }
+    }
+
+    /// Scales the vertices relative to the shape origin, fails if they end
+    /// up too close to each other.
+    pub fn scaled(&self, factor: f32) -> Result<Self, ShapeError> {
+        let f = |v| v * factor;
+        let (vertices, is_loop) = self.map_vertices(&f);
+        if is_loop {
+            Self::try_new_loop(&vertices)
+        } else {
+            Ok(self.map_ghosts(Self::try_new_chain(&vertices)?, &f))
+        }
+    }
+
+    fn map_vertices<F: Fn(Vec2) -> Vec2>(&self, f: F) -> (Vec<Vec2>, bool) {
+        let is_loop = self.is_loop();
+        let mut vertices = self.vertices();
+        if is_loop {
+            // without the repeated first vertex
+            vertices = &vertices[..vertices.len() - 1];
+        }
+        (vertices.iter().map(|&v| f(v)).collect(), is_loop)
+    }
+
+    fn map_ghosts<F: Fn(Vec2) -> Vec2>(&self, mut chain: Self, f: F) -> Self {
+        chain.set_prev_vertex(self.prev_vertex().map(&f));
+        chain.set_next_vertex(self.next_vertex().map(&f));
+        chain
+    }
+
     pub fn clear(&mut self) {
         unsafe { ffi::ChainShape_clear(self.mut_ptr()) }
     }
@@ -94,6 +155,24 @@ impl ChainShape {
     }
 }
 
+fn check_vertices(vertices: &[Vec2], min_count: usize, closed: bool) -> Result<(), ShapeError> {
+    if vertices.len() < min_count {
+        return Err(ShapeError::TooFewVertices(vertices.len()));
+    }
+    check_finite(vertices)?;
+
+    let too_close = |a: Vec2, b: Vec2| (a - b).sqr_norm() <= LINEAR_SLOP * LINEAR_SLOP;
+    for i in 1..vertices.len() {
+        if too_close(vertices[i - 1], vertices[i]) {
+            return Err(ShapeError::VerticesTooClose(i));
+        }
+    }
+    if closed && too_close(vertices[vertices.len() - 1], vertices[0]) {
+        return Err(ShapeError::VerticesTooClose(0));
+    }
+    Ok(())
+}
+
 impl Drop for ChainShape {
     fn drop(&mut self) {
         unsafe { ffi::ChainShape_drop(self.mut_ptr()) }
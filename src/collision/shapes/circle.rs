@@ -1,6 +1,6 @@
 use wrap::*;
-use common::math::Vec2;
-use super::Shape;
+use common::math::{Vec2, Transform};
+use super::{Shape, ShapeError, check_finite};
 
 wrap_shape! {
     ffi::CircleShape => CircleShape
@@ -20,6 +20,14 @@ impl CircleShape {
         circle
     }
 
+    pub fn try_new(position: Vec2, radius: f32) -> Result<Self, ShapeError> {
+        check_finite(&[position])?;
+        if !(radius >= 0.) || !radius.is_finite() {
+            return Err(ShapeError::InvalidRadius(radius));
+        }
+        Ok(Self::new_with(position, radius))
+    }
+
     pub fn support(&self, dir: &Vec2) -> i32 {
         unsafe { ffi::CircleShape_get_support(self.ptr(), dir) }
     }
@@ -55,6 +63,15 @@ impl CircleShape {
     pub fn set_position(&mut self, pos: Vec2) {
         unsafe { ffi::CircleShape_set_pos(self.mut_ptr(), pos) }
     }
+
+    pub fn transformed(&self, xf: &Transform) -> Self {
+        Self::new_with(xf * self.position(), self.radius())
+    }
+
+    /// Scales the position and radius relative to the shape origin.
+    pub fn scaled(&self, factor: f32) -> Result<Self, ShapeError> {
+        Self::try_new(self.position() * factor, self.radius() * factor.abs())
+    }
 }
 
 impl Drop for CircleShape {
@@ -0,0 +1,138 @@
+use std::mem;
+use wrap::*;
+use common::math::{Vec2, Transform};
+use collision::{RayCastInput, RayCastOutput};
+use super::{ffi, MassData, Shape, UnknownShape};
+
+/// Shapes placed relative to each other, each with its own density.
+pub struct CompoundShape {
+    parts: Vec<(UnknownShape, Transform, f32)>,
+}
+
+impl CompoundShape {
+    pub fn new() -> CompoundShape {
+        CompoundShape { parts: Vec::new() }
+    }
+
+    pub fn push<S: Into<UnknownShape>>(&mut self, shape: S, xf: Transform, density: f32) {
+        self.parts.push((shape.into(), xf, density));
+    }
+
+    pub fn with<S: Into<UnknownShape>>(mut self, shape: S, xf: Transform, density: f32) -> Self {
+        self.push(shape, xf, density);
+        self
+    }
+
+    pub fn parts(&self) -> &[(UnknownShape, Transform, f32)] {
+        &self.parts
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parts.is_empty()
+    }
+
+    /// The mass the parts give to a body when attached at its origin.
+    pub fn mass_data(&self) -> MassData {
+        let mut mass = 0.;
+        let mut center = Vec2 { x: 0., y: 0. };
+        let mut inertia = 0.;
+        for &(ref shape, ref xf, density) in &self.parts {
+            let part = shape.compute_mass(density);
+            let part_center = xf * part.center;
+            // `part.inertia` is about the origin of the part
+            inertia += part.inertia - part.mass * part.center.sqr_norm() +
+                       part.mass * part_center.sqr_norm();
+            center = center + part_center * part.mass;
+            mass += part.mass;
+        }
+
+        MassData {
+            mass: mass,
+            center: if mass > 0. { center / mass } else { center },
+            inertia: inertia,
+        }
+    }
+
+    pub fn test_point(&self, xf: &Transform, p: &Vec2) -> bool {
+        self.parts.iter().any(|&(ref shape, ref part_xf, _)| shape.test_point(&(xf * part_xf), p))
+    }
+
+    /// The closest hit along the ray, with the index of the part hit.
+    pub fn ray_cast(&self, input: &RayCastInput, xf: &Transform) -> Option<(usize, RayCastOutput)> {
+        let mut input = input.clone();
+        let mut closest = None;
+        for (i, &(ref shape, ref part_xf, _)) in self.parts.iter().enumerate() {
+            let xf = xf * part_xf;
+            for child in 0..shape.child_count() {
+                let mut output: RayCastOutput = unsafe { mem::zeroed() };
+                let hit = unsafe {
+                    ffi::Shape_ray_cast_virtual(shape.base_ptr(), &mut output, &input, &xf, child)
+                };
+                if hit {
+                    input.max_fraction = output.fraction;
+                    closest = Some((i, output));
+                }
+            }
+        }
+        closest
+    }
+
+    /// Copies of the shapes placed in the frame of the compound.
+    pub fn placed_shapes(&self) -> Vec<UnknownShape> {
+        self.parts.iter().map(|&(ref shape, ref xf, _)| shape.transformed(xf)).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use common::math::Rot;
+    use collision::shapes::PolygonShape;
+
+    fn at(x: f32, y: f32) -> Transform {
+        Transform { pos: Vec2 { x: x, y: y }, rot: Rot::from_angle(0.) }
+    }
+
+    fn dumbbell() -> CompoundShape {
+        CompoundShape::new()
+            .with(PolygonShape::new_box(0.5, 0.5), at(-1., 0.), 1.)
+            .with(PolygonShape::new_box(0.5, 0.5), at(1., 0.), 3.)
+    }
+
+    #[test]
+    fn mass() {
+        let md = dumbbell().mass_data();
+        assert!((md.mass - 4.).abs() < 1e-5);
+        assert!((md.center.x - 0.5).abs() < 1e-5 && md.center.y.abs() < 1e-5);
+        // each unit box has 1/6 of its mass about its center, shifted by 1
+        assert!((md.inertia - 4. * (1. / 6. + 1.)).abs() < 1e-4);
+
+        assert_eq!(CompoundShape::new().mass_data().mass, 0.);
+    }
+
+    #[test]
+    fn ray_cast() {
+        let compound = dumbbell();
+        let ray = |x1, x2, y| {
+            RayCastInput {
+                p1: Vec2 { x: x1, y: y },
+                p2: Vec2 { x: x2, y: y },
+                max_fraction: 1.,
+            }
+        };
+
+        let (part, output) = compound.ray_cast(&ray(-3., 3., 0.), &at(0., 0.)).unwrap();
+        assert_eq!(part, 0);
+        assert!((output.fraction - 0.25).abs() < 1e-5);
+        assert!((output.normal.x + 1.).abs() < 1e-5);
+
+        // the closest part is kept whatever the order of the parts
+        let (part, output) = compound.ray_cast(&ray(3., -3., 0.), &at(0., 0.)).unwrap();
+        assert_eq!(part, 1);
+        assert!((output.fraction - 0.25).abs() < 1e-5);
+
+        // in the frame of the compound
+        assert!(compound.ray_cast(&ray(-3., 3., 0.), &at(0., 2.)).is_none());
+        assert_eq!(compound.ray_cast(&ray(-3., 3., 2.), &at(0., 2.)).unwrap().0, 0);
+    }
+}
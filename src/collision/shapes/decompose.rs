@@ -0,0 +1,376 @@
+//! Splits simple polygons, possibly with holes, into convex polygons
+//! small enough for `PolygonShape`.
+//!
+//! The polygon is triangulated by ear clipping, holes being first bridged
+//! to the outline, then triangles sharing an edge are merged as long as
+//! the result stays convex and within `MAX_POLYGON_VERTICES` (Hertel-Mehlhorn).
+
+use std::cmp::Ordering;
+use std::f32::EPSILON;
+use common::math::{Vec2, cross_vv};
+use common::settings::MAX_POLYGON_VERTICES;
+use super::{PolygonShape, ShapeError, check_finite};
+use super::polygon::check_points;
+
+/// The outline and holes may be given in any winding order.
+pub fn decompose(outline: &[Vec2], holes: &[Vec<Vec2>]) -> Result<Vec<PolygonShape>, ShapeError> {
+    let parts = convex_parts(outline, holes)?;
+    Ok(parts.iter().map(|part| PolygonShape::new_with(part)).collect())
+}
+
+/// The vertices of the convex parts, counter-clockwise.
+/// Fails if one of them is a sliver too thin for Box2D, rather than
+/// leaving a gap in the shape.
+pub fn convex_parts(outline: &[Vec2], holes: &[Vec<Vec2>]) -> Result<Vec<Vec<Vec2>>, ShapeError> {
+    let outline = prepare(outline, true)?;
+    let holes = holes.iter()
+        .map(|hole| prepare(hole, false))
+        .collect::<Result<Vec<_>, _>>()?;
+    check_simple(&outline, &holes)?;
+
+    let polygon = bridge_holes(outline, holes)?;
+    let parts = merge(triangulate(polygon)?);
+    for part in &parts {
+        check_points(part)?;
+    }
+    if parts.is_empty() {
+        return Err(ShapeError::DegenerateHull);
+    }
+    Ok(parts)
+}
+
+fn prepare(points: &[Vec2], ccw: bool) -> Result<Vec<Vec2>, ShapeError> {
+    if points.len() < 3 {
+        return Err(ShapeError::TooFewVertices(points.len()));
+    }
+    check_finite(points)?;
+
+    let mut ring = remove_collinear(points);
+    if ring.len() < 3 {
+        return Err(ShapeError::DegenerateHull);
+    }
+
+    let area = signed_area(&ring);
+    if area.abs() <= EPSILON {
+        return Err(ShapeError::DegenerateHull);
+    }
+    if (area > 0.) != ccw {
+        ring.reverse();
+    }
+    Ok(ring)
+}
+
+fn signed_area(ring: &[Vec2]) -> f32 {
+    (0..ring.len())
+        .map(|i| 0.5 * cross_vv(ring[i], ring[(i + 1) % ring.len()]))
+        .sum()
+}
+
+fn turn(a: Vec2, b: Vec2, c: Vec2) -> f32 {
+    cross_vv(b - a, c - b)
+}
+
+/// Removes repeated vertices and vertices lying on the line of their neighbours.
+fn remove_collinear(points: &[Vec2]) -> Vec<Vec2> {
+    let mut ring = points.to_vec();
+    while ring.len() >= 3 {
+        let n = ring.len();
+        let flat = (0..n).find(|&i| {
+            let (a, b, c) = (ring[(i + n - 1) % n], ring[i], ring[(i + 1) % n]);
+            turn(a, b, c).abs() <= EPSILON * (b - a).norm() * (c - b).norm()
+        });
+        match flat {
+            Some(i) => {
+                ring.remove(i);
+            }
+            None => break,
+        }
+    }
+    ring
+}
+
+fn check_simple(outline: &[Vec2], holes: &[Vec<Vec2>]) -> Result<(), ShapeError> {
+    let rings: Vec<&[Vec2]> = Some(outline).into_iter().chain(holes.iter().map(|h| &h[..])).collect();
+    for (r, ring) in rings.iter().enumerate() {
+        for i in 0..ring.len() {
+            let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+            for (s, other) in rings.iter().enumerate().skip(r) {
+                let start = if s == r { i + 1 } else { 0 };
+                for j in start..other.len() {
+                    let (c, d) = (other[j], other[(j + 1) % other.len()]);
+                    let adjacent = s == r && (j == i + 1 || (i == 0 && j == ring.len() - 1));
+                    if !adjacent && segments_intersect(a, b, c, d) {
+                        return Err(ShapeError::SelfIntersecting);
+                    }
+                }
+            }
+        }
+    }
+
+    for (h, hole) in holes.iter().enumerate() {
+        let nested = holes.iter()
+            .enumerate()
+            .any(|(o, other)| o != h && contains(other, hole[0]));
+        if !contains(outline, hole[0]) || nested {
+            return Err(ShapeError::SelfIntersecting);
+        }
+    }
+    Ok(())
+}
+
+fn segments_intersect(a: Vec2, b: Vec2, c: Vec2, d: Vec2) -> bool {
+    let d1 = cross_vv(b - a, c - a);
+    let d2 = cross_vv(b - a, d - a);
+    let d3 = cross_vv(d - c, a - c);
+    let d4 = cross_vv(d - c, b - c);
+    if ((d1 > 0. && d2 < 0.) || (d1 < 0. && d2 > 0.)) &&
+       ((d3 > 0. && d4 < 0.) || (d3 < 0. && d4 > 0.)) {
+        return true;
+    }
+
+    (d1 == 0. && on_segment(a, b, c)) || (d2 == 0. && on_segment(a, b, d)) ||
+    (d3 == 0. && on_segment(c, d, a)) || (d4 == 0. && on_segment(c, d, b))
+}
+
+fn on_segment(a: Vec2, b: Vec2, p: Vec2) -> bool {
+    p.x >= a.x.min(b.x) && p.x <= a.x.max(b.x) && p.y >= a.y.min(b.y) && p.y <= a.y.max(b.y)
+}
+
+fn contains(ring: &[Vec2], p: Vec2) -> bool {
+    let mut inside = false;
+    for i in 0..ring.len() {
+        let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+        if (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Connects each hole to the outline by a pair of coincident edges,
+/// giving a single weakly simple polygon.
+fn bridge_holes(outline: Vec<Vec2>, mut holes: Vec<Vec<Vec2>>) -> Result<Vec<Vec2>, ShapeError> {
+    let max_x = |ring: &Vec<Vec2>| ring.iter().fold(::std::f32::MIN, |x, v| x.max(v.x));
+    holes.sort_by(|a, b| max_x(b).partial_cmp(&max_x(a)).unwrap_or(Ordering::Equal));
+
+    let mut polygon = outline;
+    while !holes.is_empty() {
+        let hole = holes.remove(0);
+        let m = (0..hole.len())
+            .max_by(|&i, &j| hole[i].x.partial_cmp(&hole[j].x).unwrap_or(Ordering::Equal))
+            .unwrap();
+        let mp = hole[m];
+
+        let mut candidates: Vec<usize> = (0..polygon.len()).collect();
+        candidates.sort_by(|&i, &j| {
+            (polygon[i] - mp).sqr_norm()
+                .partial_cmp(&(polygon[j] - mp).sqr_norm())
+                .unwrap_or(Ordering::Equal)
+        });
+        let p = candidates.into_iter()
+            .find(|&i| can_bridge(&polygon, i, &hole, mp, &holes))
+            .ok_or(ShapeError::SelfIntersecting)?;
+
+        let mut bridged = Vec::with_capacity(polygon.len() + hole.len() + 2);
+        bridged.extend_from_slice(&polygon[..p + 1]);
+        bridged.extend_from_slice(&hole[m..]);
+        bridged.extend_from_slice(&hole[..m + 1]);
+        bridged.extend_from_slice(&polygon[p..]);
+        polygon = bridged;
+    }
+    Ok(polygon)
+}
+
+fn can_bridge(polygon: &[Vec2], i: usize, hole: &[Vec2], m: Vec2, holes: &[Vec<Vec2>]) -> bool {
+    let n = polygon.len();
+    let (prev, p, next) = (polygon[(i + n - 1) % n], polygon[i], polygon[(i + 1) % n]);
+
+    // the bridge must leave `p` towards the inside of the polygon,
+    // this also picks the right copy of vertices already used by a bridge
+    let left_of_prev = cross_vv(p - prev, m - p) > 0.;
+    let left_of_next = cross_vv(next - p, m - p) > 0.;
+    let inside = if turn(prev, p, next) >= 0. {
+        left_of_prev && left_of_next
+    } else {
+        left_of_prev || left_of_next
+    };
+    if !inside {
+        return false;
+    }
+
+    let rings = Some(polygon).into_iter().chain(Some(hole)).chain(holes.iter().map(|h| &h[..]));
+    for ring in rings {
+        for j in 0..ring.len() {
+            let (c, d) = (ring[j], ring[(j + 1) % ring.len()]);
+            let incident = c == p || d == p || c == m || d == m;
+            if !incident && segments_intersect(p, m, c, d) {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+fn triangulate(mut polygon: Vec<Vec2>) -> Result<Vec<Vec<Vec2>>, ShapeError> {
+    let mut triangles = Vec::with_capacity(polygon.len());
+    while polygon.len() > 3 {
+        let n = polygon.len();
+        if let Some(i) = (0..n).find(|&i| is_ear(&polygon, i)) {
+            triangles.push(vec![polygon[(i + n - 1) % n], polygon[i], polygon[(i + 1) % n]]);
+            polygon.remove(i);
+            continue;
+        }
+
+        // only flat vertices, left around bridges, can block the clipping
+        match (0..n).find(|&i| turn(polygon[(i + n - 1) % n], polygon[i], polygon[(i + 1) % n]) == 0.) {
+            Some(i) => {
+                polygon.remove(i);
+            }
+            None => return Err(ShapeError::DegenerateHull),
+        }
+    }
+
+    if turn(polygon[0], polygon[1], polygon[2]) > 0. {
+        triangles.push(polygon);
+    }
+    Ok(triangles)
+}
+
+fn is_ear(polygon: &[Vec2], i: usize) -> bool {
+    let n = polygon.len();
+    let (a, b, c) = (polygon[(i + n - 1) % n], polygon[i], polygon[(i + 1) % n]);
+    if turn(a, b, c) <= 0. {
+        return false;
+    }
+
+    !polygon.iter().any(|&p| {
+        p != a && p != b && p != c && cross_vv(b - a, p - a) >= 0. &&
+        cross_vv(c - b, p - b) >= 0. && cross_vv(a - c, p - c) >= 0.
+    })
+}
+
+fn merge(mut parts: Vec<Vec<Vec2>>) -> Vec<Vec<Vec2>> {
+    let mut i = 0;
+    while i < parts.len() {
+        let mut merged = false;
+        let mut j = i + 1;
+        while j < parts.len() {
+            match try_merge(&parts[i], &parts[j]) {
+                Some(part) => {
+                    parts[i] = part;
+                    parts.swap_remove(j);
+                    merged = true;
+                }
+                None => j += 1,
+            }
+        }
+
+        if !merged {
+            i += 1;
+        }
+    }
+    parts
+}
+
+fn try_merge(a: &[Vec2], b: &[Vec2]) -> Option<Vec<Vec2>> {
+    let (na, nb) = (a.len(), b.len());
+    for k in 0..na {
+        let (p, q) = (a[k], a[(k + 1) % na]);
+        let l = match (0..nb).find(|&l| b[l] == q && b[(l + 1) % nb] == p) {
+            Some(l) => l,
+            None => continue,
+        };
+
+        let mut points: Vec<Vec2> = (1..na + 1).map(|s| a[(k + s) % na]).collect();
+        points.extend((2..nb).map(|s| b[(l + s) % nb]));
+
+        let has_duplicates = (0..points.len()).any(|i| points[i + 1..].contains(&points[i]));
+        if has_duplicates {
+            continue;
+        }
+
+        let points = remove_collinear(&points);
+        let n = points.len();
+        let convex = (0..n).all(|i| turn(points[(i + n - 1) % n], points[i], points[(i + 1) % n]) > 0.);
+        if convex && n <= MAX_POLYGON_VERTICES {
+            return Some(points);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn v(x: f32, y: f32) -> Vec2 {
+        Vec2 { x: x, y: y }
+    }
+
+    fn check(parts: &[Vec<Vec2>], area: f32) {
+        for part in parts {
+            assert!(part.len() <= MAX_POLYGON_VERTICES);
+            let n = part.len();
+            assert!((0..n).all(|i| turn(part[(i + n - 1) % n], part[i], part[(i + 1) % n]) > 0.));
+        }
+        let total: f32 = parts.iter().map(|part| signed_area(part)).sum();
+        assert!((total - area).abs() < 1e-3, "area {} instead of {}", total, area);
+    }
+
+    #[test]
+    fn convex_stays_whole() {
+        let square = [v(0., 0.), v(0., 1.), v(1., 1.), v(1., 0.)];
+        let parts = convex_parts(&square, &[]).unwrap();
+        assert_eq!(parts.len(), 1);
+        check(&parts, 1.);
+    }
+
+    #[test]
+    fn concave_outline() {
+        let l = [v(0., 0.), v(2., 0.), v(2., 1.), v(1., 1.), v(1., 2.), v(0., 2.)];
+        let parts = convex_parts(&l, &[]).unwrap();
+        assert_eq!(parts.len(), 2);
+        check(&parts, 3.);
+
+        let star: Vec<_> = (0..20)
+            .map(|i| {
+                let angle = i as f32 * ::std::f32::consts::PI / 10.;
+                let r = if i % 2 == 0 { 2. } else { 1. };
+                v(r * angle.cos(), r * angle.sin())
+            })
+            .collect();
+        let area = signed_area(&star);
+        check(&convex_parts(&star, &[]).unwrap(), area);
+    }
+
+    #[test]
+    fn holes() {
+        let outline = [v(0., 0.), v(4., 0.), v(4., 4.), v(0., 4.)];
+        let holes = vec![vec![v(1., 1.), v(2., 1.), v(2., 2.), v(1., 2.)],
+                         vec![v(2.5, 2.5), v(3.5, 2.5), v(3.5, 3.5), v(2.5, 3.5)]];
+        check(&convex_parts(&outline, &holes).unwrap(), 14.);
+    }
+
+    #[test]
+    fn invalid_outlines() {
+        assert_eq!(convex_parts(&[v(0., 0.), v(1., 0.)], &[]),
+                   Err(ShapeError::TooFewVertices(2)));
+        assert_eq!(convex_parts(&[v(0., 0.), v(1., 0.), v(2., 0.)], &[]),
+                   Err(ShapeError::DegenerateHull));
+
+        let bow_tie = [v(0., 0.), v(2., 2.), v(2., 0.), v(0., 1.)];
+        assert_eq!(convex_parts(&bow_tie, &[]), Err(ShapeError::SelfIntersecting));
+
+        let square = [v(0., 0.), v(1., 0.), v(1., 1.), v(0., 1.)];
+        let outside = vec![vec![v(2., 2.), v(3., 2.), v(3., 3.)]];
+        assert_eq!(convex_parts(&square, &outside), Err(ShapeError::SelfIntersecting));
+    }
+
+    #[test]
+    fn sliver_parts() {
+        // the spike cannot be merged with the square and is too thin on its own
+        let spiked = [v(0., 0.), v(1., 0.), v(1., 1.), v(0.501, 1.), v(0.5005, 3.), v(0.5, 1.),
+                      v(0., 1.)];
+        assert_eq!(convex_parts(&spiked, &[]), Err(ShapeError::DegenerateHull));
+    }
+}
@@ -0,0 +1,180 @@
+use std::convert::TryFrom;
+use wrap::*;
+use common::math::Vec2;
+use common::settings::POLYGON_RADIUS;
+use super::{ffi, Shape, ShapeError, UnknownShape, CircleShape, EdgeShape, PolygonShape,
+            ChainShape};
+
+/// A shape stored as plain data, independent of Box2D's allocations.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum ShapeDesc {
+    Circle { position: Vec2, radius: f32 },
+    Edge {
+        v1: Vec2,
+        v2: Vec2,
+        v0: Option<Vec2>,
+        v3: Option<Vec2>,
+    },
+    Polygon { vertices: Vec<Vec2>, radius: f32 },
+    Chain {
+        /// Loops do not repeat their first vertex here.
+        vertices: Vec<Vec2>,
+        is_loop: bool,
+        prev_vertex: Option<Vec2>,
+        next_vertex: Option<Vec2>,
+    },
+}
+
+impl ShapeDesc {
+    pub fn polygon(vertices: Vec<Vec2>) -> ShapeDesc {
+        ShapeDesc::Polygon {
+            vertices: vertices,
+            radius: POLYGON_RADIUS,
+        }
+    }
+
+    pub fn to_shape(&self) -> UnknownShape {
+        match *self {
+            ShapeDesc::Circle { position, radius } => {
+                UnknownShape::Circle(CircleShape::new_with(position, radius))
+            }
+            ShapeDesc::Edge { v1, v2, v0, v3 } => {
+                let mut edge = EdgeShape::new_with(&v1, &v2);
+                edge.set_v0(v0);
+                edge.set_v3(v3);
+                UnknownShape::Edge(edge)
+            }
+            ShapeDesc::Polygon { ref vertices, radius } => {
+                let mut polygon = PolygonShape::new_with(vertices);
+                unsafe { ffi::Shape_set_radius(polygon.mut_base_ptr(), radius) }
+                UnknownShape::Polygon(polygon)
+            }
+            ShapeDesc::Chain { ref vertices, is_loop, prev_vertex, next_vertex } => {
+                UnknownShape::Chain(if is_loop {
+                    ChainShape::new_loop(vertices)
+                } else {
+                    let mut chain = ChainShape::new_chain(vertices);
+                    chain.set_prev_vertex(prev_vertex);
+                    chain.set_next_vertex(next_vertex);
+                    chain
+                })
+            }
+        }
+    }
+
+    /// Like `to_shape` but checks the data first, see `ShapeError`.
+    pub fn try_to_shape(&self) -> Result<UnknownShape, ShapeError> {
+        match *self {
+            ShapeDesc::Circle { position, radius } => {
+                CircleShape::try_new(position, radius).map(UnknownShape::Circle)
+            }
+            ShapeDesc::Edge { v1, v2, v0, v3 } => {
+                let vertices: Vec<_> = v0.into_iter().chain(vec![v1, v2]).chain(v3).collect();
+                super::check_finite(&vertices)?;
+                Ok(self.to_shape())
+            }
+            ShapeDesc::Polygon { ref vertices, radius } => {
+                if !(radius >= 0.) || !radius.is_finite() {
+                    return Err(ShapeError::InvalidRadius(radius));
+                }
+                super::polygon::check_points(vertices)?;
+                Ok(self.to_shape())
+            }
+            ShapeDesc::Chain { ref vertices, is_loop, .. } => {
+                if is_loop {
+                    ChainShape::try_new_loop(vertices)?;
+                } else {
+                    ChainShape::try_new_chain(vertices)?;
+                }
+                Ok(self.to_shape())
+            }
+        }
+    }
+}
+
+impl<'a> From<&'a CircleShape> for ShapeDesc {
+    fn from(circle: &'a CircleShape) -> ShapeDesc {
+        ShapeDesc::Circle {
+            position: circle.position(),
+            radius: circle.radius(),
+        }
+    }
+}
+
+impl<'a> From<&'a EdgeShape> for ShapeDesc {
+    fn from(edge: &'a EdgeShape) -> ShapeDesc {
+        ShapeDesc::Edge {
+            v1: edge.v1(),
+            v2: edge.v2(),
+            v0: edge.v0(),
+            v3: edge.v3(),
+        }
+    }
+}
+
+impl<'a> From<&'a PolygonShape> for ShapeDesc {
+    fn from(polygon: &'a PolygonShape) -> ShapeDesc {
+        ShapeDesc::Polygon {
+            vertices: (0..polygon.vertex_count()).map(|i| *polygon.vertex(i)).collect(),
+            radius: Shape::radius(polygon),
+        }
+    }
+}
+
+impl<'a> From<&'a ChainShape> for ShapeDesc {
+    fn from(chain: &'a ChainShape) -> ShapeDesc {
+        let vertices = chain.vertices();
+        if chain.is_loop() {
+            ShapeDesc::Chain {
+                vertices: vertices[..vertices.len() - 1].to_vec(),
+                is_loop: true,
+                prev_vertex: None,
+                next_vertex: None,
+            }
+        } else {
+            ShapeDesc::Chain {
+                vertices: vertices.to_vec(),
+                is_loop: false,
+                prev_vertex: chain.prev_vertex(),
+                next_vertex: chain.next_vertex(),
+            }
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a UnknownShape> for ShapeDesc {
+    type Error = ShapeError;
+
+    fn try_from(shape: &'a UnknownShape) -> Result<ShapeDesc, ShapeError> {
+        match *shape {
+            UnknownShape::Circle(ref x) => Ok(x.into()),
+            UnknownShape::Edge(ref x) => Ok(x.into()),
+            UnknownShape::Polygon(ref x) => Ok(x.into()),
+            UnknownShape::Chain(ref x) => Ok(x.into()),
+            UnknownShape::Unknown => Err(ShapeError::UnknownShape),
+        }
+    }
+}
+
+impl<'a> From<&'a ShapeDesc> for UnknownShape {
+    fn from(desc: &'a ShapeDesc) -> UnknownShape {
+        desc.to_shape()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_send<T: Send + Clone + PartialEq>() {}
+
+    #[test]
+    fn plain_data() {
+        assert_send::<ShapeDesc>();
+        let square = ShapeDesc::polygon(vec![Vec2 { x: 0., y: 0. },
+                                             Vec2 { x: 1., y: 0. },
+                                             Vec2 { x: 1., y: 1. }]);
+        assert_eq!(square.clone(), square);
+    }
+}
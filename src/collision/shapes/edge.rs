@@ -2,8 +2,9 @@ use std::mem;
 use std::ptr;
 
 use wrap::*;
-use common::math::Vec2;
-use super::Shape;
+use common::math::{Vec2, Transform};
+use common::settings::LINEAR_SLOP;
+use super::{Shape, ShapeError, check_finite};
 
 wrap_shape! {
     ffi::EdgeShape => EdgeShape
@@ -22,10 +23,37 @@ impl EdgeShape {
         s
     }
 
+    /// Checks that the vertices are finite and apart.
+    pub fn try_new_with(v1: &Vec2, v2: &Vec2) -> Result<Self, ShapeError> {
+        check_finite(&[*v1, *v2])?;
+        if (*v2 - *v1).sqr_norm() <= LINEAR_SLOP * LINEAR_SLOP {
+            return Err(ShapeError::VerticesTooClose(1));
+        }
+        Ok(Self::new_with(v1, v2))
+    }
+
     pub fn set(&mut self, v1: &Vec2, v2: &Vec2) {
         unsafe { ffi::EdgeShape_set(self.mut_ptr(), v1, v2) }
     }
 
+    pub fn transformed(&self, xf: &Transform) -> Self {
+        let f = |v| xf * v;
+        self.map_ghosts(Self::new_with(&f(self.v1()), &f(self.v2())), &f)
+    }
+
+    /// Scales the vertices relative to the shape origin, fails if they end
+    /// up too close to each other.
+    pub fn scaled(&self, factor: f32) -> Result<Self, ShapeError> {
+        let f = |v| v * factor;
+        Ok(self.map_ghosts(Self::try_new_with(&f(self.v1()), &f(self.v2()))?, &f))
+    }
+
+    fn map_ghosts<F: Fn(Vec2) -> Vec2>(&self, mut edge: Self, f: F) -> Self {
+        edge.set_v0(self.v0().map(&f));
+        edge.set_v3(self.v3().map(&f));
+        edge
+    }
+
     pub fn v1(&self) -> Vec2 {
         unsafe { ffi::EdgeShape_get_v1(self.ptr()) }
     }
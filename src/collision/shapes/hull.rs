@@ -0,0 +1,175 @@
+//! Rust side hull computation, to build polygons from point clouds
+//! knowing which points are kept.
+
+use std::cmp::Ordering;
+use common::math::{Vec2, cross_vv};
+use common::settings::{LINEAR_SLOP, MAX_POLYGON_VERTICES};
+
+/// The convex hull of `points`, counter-clockwise and without collinear vertices.
+/// It has less than 3 vertices when the points are collinear.
+pub fn convex_hull(points: &[Vec2]) -> Vec<Vec2> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| {
+        a.x.partial_cmp(&b.x)
+            .unwrap_or(Ordering::Equal)
+            .then(a.y.partial_cmp(&b.y).unwrap_or(Ordering::Equal))
+    });
+    sorted.dedup();
+    if sorted.len() < 3 {
+        return sorted;
+    }
+
+    // Andrew's monotone chain
+    let mut hull: Vec<Vec2> = Vec::with_capacity(2 * sorted.len());
+    for pass in 0..2 {
+        let start = hull.len();
+        for &p in &sorted {
+            while hull.len() >= start + 2 &&
+                  cross_vv(hull[hull.len() - 1] - hull[hull.len() - 2],
+                           p - hull[hull.len() - 1]) <= 0. {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        // the last point starts the other chain
+        hull.pop();
+        if pass == 0 {
+            sorted.reverse();
+        }
+    }
+    hull
+}
+
+/// Keeps the first of the points closer than `distance` to each other.
+pub fn weld(points: &[Vec2], distance: f32) -> Vec<Vec2> {
+    let mut welded: Vec<Vec2> = Vec::with_capacity(points.len());
+    for &p in points {
+        if welded.iter().all(|&q| (p - q).sqr_norm() > distance * distance) {
+            welded.push(p);
+        }
+    }
+    welded
+}
+
+/// Ramer-Douglas-Peucker simplification of the closed polygon `ring`,
+/// vertices closer than `tolerance` to the simplified outline are removed.
+pub fn simplify(ring: &[Vec2], tolerance: f32) -> Vec<Vec2> {
+    rdp(ring, |_, dist| dist > tolerance)
+}
+
+/// Like `simplify` but keeps the `max_vertices` most significant vertices.
+/// The result stays convex when `ring` is.
+pub fn simplify_to(ring: &[Vec2], max_vertices: usize) -> Vec<Vec2> {
+    rdp(ring, |kept, dist| kept < max_vertices && dist > 0.)
+}
+
+/// Welds the points within `LINEAR_SLOP`, computes their hull and reduces it
+/// to `MAX_POLYGON_VERTICES` vertices, ready for `PolygonShape::set`.
+pub fn polygon_hull(points: &[Vec2]) -> Vec<Vec2> {
+    let hull = convex_hull(&weld(points, LINEAR_SLOP));
+    simplify_to(&hull, MAX_POLYGON_VERTICES)
+}
+
+fn rdp<F>(ring: &[Vec2], mut keep: F) -> Vec<Vec2>
+    where F: FnMut(usize, f32) -> bool
+{
+    let n = ring.len();
+    if n < 3 {
+        return ring.to_vec();
+    }
+
+    // start from the two vertices farthest from each other
+    let far = |from: usize| {
+        (0..n)
+            .max_by(|&i, &j| {
+                (ring[i] - ring[from]).sqr_norm()
+                    .partial_cmp(&(ring[j] - ring[from]).sqr_norm())
+                    .unwrap_or(Ordering::Equal)
+            })
+            .unwrap()
+    };
+    let a = far(0);
+    let b = far(a);
+
+    let mut kept = vec![false; n];
+    kept[a] = true;
+    kept[b] = true;
+    let mut count = if a == b { 1 } else { 2 };
+
+    loop {
+        let mut best: Option<(usize, f32)> = None;
+        for start in (0..n).filter(|&i| kept[i]) {
+            let end = (1..n + 1).map(|k| (start + k) % n).find(|&i| kept[i]).unwrap();
+            let mut i = (start + 1) % n;
+            while i != end {
+                let dist = segment_distance(ring[i], ring[start], ring[end]);
+                if best.map_or(true, |(_, d)| dist > d) {
+                    best = Some((i, dist));
+                }
+                i = (i + 1) % n;
+            }
+        }
+
+        match best {
+            Some((i, dist)) if keep(count, dist) => {
+                kept[i] = true;
+                count += 1;
+            }
+            _ => break,
+        }
+    }
+
+    (0..n).filter(|&i| kept[i]).map(|i| ring[i]).collect()
+}
+
+fn segment_distance(p: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let ab = b - a;
+    let len = ab.sqr_norm();
+    if len == 0. {
+        return (p - a).norm();
+    }
+    let t = ((p.x - a.x) * ab.x + (p.y - a.y) * ab.y) / len;
+    let t = t.max(0.).min(1.);
+    (p - (a + ab * t)).norm()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn v(x: f32, y: f32) -> Vec2 {
+        Vec2 { x: x, y: y }
+    }
+
+    #[test]
+    fn hull_drops_inner_and_collinear_points() {
+        let points = [v(0., 0.), v(1., 0.), v(2., 0.), v(2., 2.), v(1., 1.), v(0., 2.), v(0., 1.)];
+        assert_eq!(convex_hull(&points), vec![v(0., 0.), v(2., 0.), v(2., 2.), v(0., 2.)]);
+
+        assert_eq!(convex_hull(&[v(0., 0.), v(1., 1.), v(2., 2.)]).len(), 2);
+        assert_eq!(convex_hull(&[v(1., 1.), v(1., 1.), v(1., 1.)]), vec![v(1., 1.)]);
+    }
+
+    #[test]
+    fn weld_keeps_first() {
+        let points = [v(0., 0.), v(0.001, 0.), v(1., 0.)];
+        assert_eq!(weld(&points, LINEAR_SLOP), vec![v(0., 0.), v(1., 0.)]);
+    }
+
+    #[test]
+    fn simplification() {
+        let square = [v(0., 0.), v(1., 0.01), v(2., 0.), v(2., 2.), v(0., 2.)];
+        assert_eq!(simplify(&square, 0.1).len(), 4);
+        assert_eq!(simplify(&square, 0.001).len(), 5);
+
+        let circle: Vec<_> = (0..64)
+            .map(|i| {
+                let angle = i as f32 * 2. * ::std::f32::consts::PI / 64.;
+                v(angle.cos(), angle.sin())
+            })
+            .collect();
+        let reduced = polygon_hull(&circle);
+        assert_eq!(reduced.len(), MAX_POLYGON_VERTICES);
+        assert_eq!(convex_hull(&reduced).len(), MAX_POLYGON_VERTICES);
+    }
+}
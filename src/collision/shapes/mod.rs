@@ -1,4 +1,6 @@
 use std::mem;
+use std::fmt;
+use std::error;
 use wrap::*;
 use common::math::{Vec2, Transform};
 use collision::{RayCastInput, RayCastOutput, AABB};
@@ -23,11 +25,65 @@ pub mod chain;
 pub mod edge;
 pub mod circle;
 pub mod polygon;
+pub mod decompose;
+pub mod hull;
+pub mod rounded;
+pub mod compound;
+pub mod desc;
 
 pub use self::chain::ChainShape;
 pub use self::circle::CircleShape;
 pub use self::edge::EdgeShape;
 pub use self::polygon::PolygonShape;
+pub use self::compound::CompoundShape;
+pub use self::desc::ShapeDesc;
+
+/// Why a shape definition would be rejected by Box2D.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ShapeError {
+    TooFewVertices(usize),
+    TooManyVertices(usize),
+    NonFiniteVertex(usize),
+    /// The vertices at this index and the previous one are too close.
+    VerticesTooClose(usize),
+    /// The vertices do not span a convex hull with a positive area.
+    DegenerateHull,
+    InvalidRadius(f32),
+    /// A half length or half extent that is negative, zero where it must not
+    /// be, or not finite.
+    InvalidExtent(f32),
+    /// The outline crosses itself or its holes, or a hole is not inside it.
+    SelfIntersecting,
+    /// `UnknownShape::Unknown`, which has no data.
+    UnknownShape,
+}
+
+impl fmt::Display for ShapeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ShapeError::TooFewVertices(n) => write!(f, "too few vertices: {}", n),
+            ShapeError::TooManyVertices(n) => write!(f, "too many vertices: {}", n),
+            ShapeError::NonFiniteVertex(i) => write!(f, "vertex {} is not finite", i),
+            ShapeError::VerticesTooClose(i) => {
+                write!(f, "vertex {} is too close to the previous one", i)
+            }
+            ShapeError::DegenerateHull => write!(f, "degenerate convex hull"),
+            ShapeError::InvalidRadius(r) => write!(f, "invalid radius: {}", r),
+            ShapeError::InvalidExtent(e) => write!(f, "invalid extent: {}", e),
+            ShapeError::SelfIntersecting => write!(f, "self-intersecting outline"),
+            ShapeError::UnknownShape => write!(f, "truly unknown shape"),
+        }
+    }
+}
+
+impl error::Error for ShapeError {}
+
+fn check_finite(vertices: &[Vec2]) -> Result<(), ShapeError> {
+    match vertices.iter().position(|v| !v.x.is_finite() || !v.y.is_finite()) {
+        Some(i) => Err(ShapeError::NonFiniteVertex(i)),
+        None => Ok(()),
+    }
+}
 
 #[repr(C)]
 #[derive(Clone, Debug)]
@@ -52,6 +108,10 @@ pub trait Shape: WrappedBase<ffi::Shape> {
         unsafe { ffi::Shape_get_type(self.base_ptr()) }
     }
 
+    fn radius(&self) -> f32 {
+        unsafe { ffi::Shape_get_radius(self.base_ptr()) }
+    }
+
     fn child_count(&self) -> i32 {
         unsafe { ffi::Shape_get_child_count_virtual(self.base_ptr()) }
     }
@@ -142,6 +202,54 @@ impl FromFFI<ffi::Shape> for UnknownShape {
 
 impl Shape for UnknownShape {}
 
+impl UnknownShape {
+    pub fn transformed(&self, xf: &Transform) -> UnknownShape {
+        use self::UnknownShape::*;
+        match self {
+            &Circle(ref x) => Circle(x.transformed(xf)),
+            &Edge(ref x) => Edge(x.transformed(xf)),
+            &Polygon(ref x) => Polygon(x.transformed(xf)),
+            &Chain(ref x) => Chain(x.transformed(xf)),
+            &Unknown => Unknown,
+        }
+    }
+
+    pub fn scaled(&self, factor: f32) -> Result<UnknownShape, ShapeError> {
+        use self::UnknownShape::*;
+        Ok(match self {
+            &Circle(ref x) => Circle(x.scaled(factor)?),
+            &Edge(ref x) => Edge(x.scaled(factor)?),
+            &Polygon(ref x) => Polygon(x.scaled(factor)?),
+            &Chain(ref x) => Chain(x.scaled(factor)?),
+            &Unknown => Unknown,
+        })
+    }
+}
+
+impl From<CircleShape> for UnknownShape {
+    fn from(shape: CircleShape) -> UnknownShape {
+        UnknownShape::Circle(shape)
+    }
+}
+
+impl From<EdgeShape> for UnknownShape {
+    fn from(shape: EdgeShape) -> UnknownShape {
+        UnknownShape::Edge(shape)
+    }
+}
+
+impl From<PolygonShape> for UnknownShape {
+    fn from(shape: PolygonShape) -> UnknownShape {
+        UnknownShape::Polygon(shape)
+    }
+}
+
+impl From<ChainShape> for UnknownShape {
+    fn from(shape: ChainShape) -> UnknownShape {
+        UnknownShape::Chain(shape)
+    }
+}
+
 #[doc(hidden)]
 pub mod ffi {
     use common::math::{Vec2, Transform};
@@ -1,6 +1,8 @@
 use wrap::*;
-use common::math::Vec2;
-use super::Shape;
+use common::math::{Vec2, Transform, cross_vv};
+use common::settings::{LINEAR_SLOP, MAX_POLYGON_VERTICES};
+use super::{Shape, ShapeError, check_finite};
+use super::hull::{convex_hull, weld, polygon_hull};
 
 wrap_shape! {
     ffi::PolygonShape => PolygonShape
@@ -31,6 +33,19 @@ impl PolygonShape {
         s
     }
     
+    /// Checks the points the way `b2PolygonShape::Set` asserts on them.
+    pub fn try_new_with(points: &[Vec2]) -> Result<Self, ShapeError> {
+        check_points(points)?;
+        Ok(Self::new_with(points))
+    }
+
+    /// Builds the polygon from the hull of any number of points,
+    /// see `hull::polygon_hull`.
+    pub fn try_new_hull(points: &[Vec2]) -> Result<Self, ShapeError> {
+        check_finite(points)?;
+        Self::try_new_with(&polygon_hull(points))
+    }
+
     pub fn new_box(hw: f32, hh: f32) -> Self {
         let mut s = Self::new();
         s.set_as_box(hw, hh);
@@ -43,6 +58,20 @@ impl PolygonShape {
         s
     }
 
+    pub fn transformed(&self, xf: &Transform) -> Self {
+        Self::new_with(&self.map_vertices(|v| xf * v))
+    }
+
+    /// Scales the vertices relative to the shape origin, fails if they
+    /// collapse.
+    pub fn scaled(&self, factor: f32) -> Result<Self, ShapeError> {
+        Self::try_new_with(&self.map_vertices(|v| v * factor))
+    }
+
+    fn map_vertices<F: Fn(Vec2) -> Vec2>(&self, f: F) -> Vec<Vec2> {
+        (0..self.vertex_count()).map(|i| f(*self.vertex(i))).collect()
+    }
+
     pub fn vertex_count(&self) -> i32 {
         unsafe { ffi::PolygonShape_get_vertex_count(self.ptr()) }
     }
@@ -58,6 +87,34 @@ impl PolygonShape {
     }
 }
 
+/// Welds the points like `b2PolygonShape::Set` and requires their hull to
+/// have a positive area. Box2D gift wraps where `hull::convex_hull` uses a
+/// monotone chain, both drop collinear points so they agree on degenerate
+/// inputs.
+#[doc(hidden)]
+pub fn check_points(points: &[Vec2]) -> Result<(), ShapeError> {
+    if points.len() < 3 {
+        return Err(ShapeError::TooFewVertices(points.len()));
+    }
+    if points.len() > MAX_POLYGON_VERTICES {
+        return Err(ShapeError::TooManyVertices(points.len()));
+    }
+    check_finite(points)?;
+
+    let hull = convex_hull(&weld(points, 0.5 * LINEAR_SLOP));
+    if hull.len() < 3 {
+        return Err(ShapeError::DegenerateHull);
+    }
+
+    let area: f32 = (1..hull.len() - 1)
+        .map(|i| 0.5 * cross_vv(hull[i] - hull[0], hull[i + 1] - hull[0]))
+        .sum();
+    if area <= ::std::f32::EPSILON {
+        return Err(ShapeError::DegenerateHull);
+    }
+    Ok(())
+}
+
 impl Drop for PolygonShape {
     fn drop(&mut self) {
         unsafe { ffi::PolygonShape_drop(self.mut_ptr()) }
@@ -88,3 +145,38 @@ pub mod ffi {
         pub fn PolygonShape_validate(slf: *const PolygonShape) -> bool;
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn v(x: f32, y: f32) -> Vec2 {
+        Vec2 { x: x, y: y }
+    }
+
+    #[test]
+    fn points() {
+        assert!(check_points(&[v(0., 0.), v(1., 0.), v(0., 1.)]).is_ok());
+        // order and inner points do not matter
+        assert!(check_points(&[v(0., 1.), v(0.2, 0.2), v(1., 0.), v(0., 0.)]).is_ok());
+
+        assert_eq!(check_points(&[v(0., 0.), v(1., 0.)]), Err(ShapeError::TooFewVertices(2)));
+        let many: Vec<_> = (0..MAX_POLYGON_VERTICES + 1).map(|i| v(i as f32, 0.)).collect();
+        assert_eq!(check_points(&many), Err(ShapeError::TooManyVertices(many.len())));
+        assert_eq!(check_points(&[v(0., 0.), v(1., ::std::f32::NAN), v(0., 1.)]),
+                   Err(ShapeError::NonFiniteVertex(1)));
+    }
+
+    #[test]
+    fn degenerate_points() {
+        // collinear
+        assert_eq!(check_points(&[v(0., 0.), v(1., 1.), v(2., 2.), v(3., 3.)]),
+                   Err(ShapeError::DegenerateHull));
+        // welded into two points
+        assert_eq!(check_points(&[v(0., 0.), v(1., 0.), v(1., 0.001)]),
+                   Err(ShapeError::DegenerateHull));
+        // a hull too thin to have an area
+        assert_eq!(check_points(&[v(0., 0.), v(1., 0.), v(0.5, 1e-7)]),
+                   Err(ShapeError::DegenerateHull));
+    }
+}
@@ -0,0 +1,223 @@
+//! Shapes with round edges built from several fixtures, since Box2D polygons
+//! only have the small `POLYGON_RADIUS` skin.
+
+use std::f32::consts::PI;
+use common::math::{Vec2, Rot, Transform};
+use super::{MassData, ShapeError, UnknownShape, CircleShape, PolygonShape};
+
+/// Shapes attached together by `MetaBody::create_fixture_group`.
+pub trait ShapeGroup {
+    fn shapes(&self) -> Vec<UnknownShape>;
+
+    /// The mass of the union of the shapes, which overlap.
+    fn mass_data(&self, density: f32) -> MassData;
+}
+
+/// A box with two half circles at the ends of its local x axis.
+#[derive(Clone, Debug)]
+pub struct Capsule {
+    pub center: Vec2,
+    pub angle: f32,
+    /// Half the distance between the centers of the circles.
+    pub half_length: f32,
+    pub radius: f32,
+}
+
+impl Capsule {
+    pub fn new(half_length: f32, radius: f32) -> Capsule {
+        Capsule::try_new(half_length, radius).expect("invalid capsule")
+    }
+
+    pub fn try_new(half_length: f32, radius: f32) -> Result<Capsule, ShapeError> {
+        if !(half_length >= 0.) || !half_length.is_finite() {
+            return Err(ShapeError::InvalidExtent(half_length));
+        }
+        if !(radius > 0.) || !radius.is_finite() {
+            return Err(ShapeError::InvalidRadius(radius));
+        }
+        Ok(Capsule {
+            center: Vec2 { x: 0., y: 0. },
+            angle: 0.,
+            half_length: half_length,
+            radius: radius,
+        })
+    }
+}
+
+impl ShapeGroup for Capsule {
+    fn shapes(&self) -> Vec<UnknownShape> {
+        let xf = transform(self.center, self.angle);
+        let (h, r) = (self.half_length, self.radius);
+        let mut shapes: Vec<_> = [-h, h]
+            .iter()
+            .map(|&x| UnknownShape::Circle(CircleShape::new_with(&xf * Vec2 { x: x, y: 0. }, r)))
+            .collect();
+        if h > 0. {
+            shapes.push(UnknownShape::Polygon(PolygonShape::new_oriented_box(h,
+                                                                             r,
+                                                                             &self.center,
+                                                                             self.angle)));
+        }
+        shapes
+    }
+
+    fn mass_data(&self, density: f32) -> MassData {
+        let (h, r) = (self.half_length, self.radius);
+        let rect = density * 4. * h * r;
+        let disk = density * PI * r * r;
+        // each half disk has its centroid at `d` from its flat side
+        let d = 4. * r / (3. * PI);
+        let inertia = rect * (4. * h * h + 4. * r * r) / 12. +
+                      disk * (0.5 * r * r + h * h + 2. * h * d);
+        centered_at(self.center, rect + disk, inertia)
+    }
+}
+
+/// A box of positive half extents `half_width` and `half_height` with corners
+/// rounded by `radius`, which must not exceed the half extents.
+#[derive(Clone, Debug)]
+pub struct RoundedBox {
+    pub center: Vec2,
+    pub angle: f32,
+    pub half_width: f32,
+    pub half_height: f32,
+    pub radius: f32,
+}
+
+impl RoundedBox {
+    pub fn new(half_width: f32, half_height: f32, radius: f32) -> RoundedBox {
+        RoundedBox::try_new(half_width, half_height, radius).expect("invalid rounded box")
+    }
+
+    pub fn try_new(half_width: f32,
+                   half_height: f32,
+                   radius: f32)
+                   -> Result<RoundedBox, ShapeError> {
+        for &extent in &[half_width, half_height] {
+            if !(extent > 0.) || !extent.is_finite() {
+                return Err(ShapeError::InvalidExtent(extent));
+            }
+        }
+        if !(radius >= 0. && radius <= half_width && radius <= half_height) {
+            return Err(ShapeError::InvalidRadius(radius));
+        }
+        Ok(RoundedBox {
+            center: Vec2 { x: 0., y: 0. },
+            angle: 0.,
+            half_width: half_width,
+            half_height: half_height,
+            radius: radius,
+        })
+    }
+}
+
+impl ShapeGroup for RoundedBox {
+    fn shapes(&self) -> Vec<UnknownShape> {
+        let xf = transform(self.center, self.angle);
+        let r = self.radius;
+        let (a, b) = (self.half_width - r, self.half_height - r);
+
+        // boxes without width would trip Box2D assertions
+        let mut shapes = Vec::new();
+        if b > 0. {
+            shapes.push(UnknownShape::Polygon(PolygonShape::new_oriented_box(self.half_width,
+                                                                             b,
+                                                                             &self.center,
+                                                                             self.angle)));
+        }
+        if r > 0. {
+            if a > 0. {
+                shapes.push(UnknownShape::Polygon(PolygonShape::new_oriented_box(a,
+                                                                                 self.half_height,
+                                                                                 &self.center,
+                                                                                 self.angle)));
+            }
+            for &(x, y) in &[(a, b), (-a, b), (-a, -b), (a, -b)] {
+                let position = &xf * Vec2 { x: x, y: y };
+                shapes.push(UnknownShape::Circle(CircleShape::new_with(position, r)));
+            }
+        }
+        shapes
+    }
+
+    fn mass_data(&self, density: f32) -> MassData {
+        let r = self.radius;
+        let (a, b) = (self.half_width - r, self.half_height - r);
+
+        let core = density * 4. * a * b;
+        let top = density * 2. * a * r;
+        let side = density * 2. * b * r;
+        let quarter = density * PI * r * r / 4.;
+        // each quarter disk has its centroid at `(d, d)` from its corner
+        let d = 4. * r / (3. * PI);
+
+        let mass = core + 2. * top + 2. * side + 4. * quarter;
+        let inertia = core * (4. * a * a + 4. * b * b) / 12. +
+                      2. * top * ((4. * a * a + r * r) / 12. + (b + 0.5 * r) * (b + 0.5 * r)) +
+                      2. * side * ((r * r + 4. * b * b) / 12. + (a + 0.5 * r) * (a + 0.5 * r)) +
+                      4. * quarter * (0.5 * r * r - 2. * d * d + (a + d) * (a + d) + (b + d) * (b + d));
+        centered_at(self.center, mass, inertia)
+    }
+}
+
+fn transform(center: Vec2, angle: f32) -> Transform {
+    Transform {
+        pos: center,
+        rot: Rot::from_angle(angle),
+    }
+}
+
+/// `inertia` is about the centroid, `MassData` wants it about the origin.
+fn centered_at(center: Vec2, mass: f32, inertia: f32) -> MassData {
+    MassData {
+        mass: mass,
+        center: center,
+        inertia: inertia + mass * center.sqr_norm(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn degenerate_cases() {
+        // a capsule without length is a disk
+        let disk = Capsule::new(0., 2.).mass_data(1.);
+        assert!((disk.mass - PI * 4.).abs() < 1e-4);
+        assert!((disk.inertia - 0.5 * disk.mass * 4.).abs() < 1e-3);
+
+        // a box without rounding
+        let mut rounded = RoundedBox::new(2., 1., 0.);
+        rounded.center = Vec2 { x: 1., y: 0. };
+        let data = rounded.mass_data(2.);
+        assert!((data.mass - 16.).abs() < 1e-4);
+        assert!((data.inertia - (16. * (16. + 4.) / 12. + 16.)).abs() < 1e-3);
+
+        // a rounded box as round as possible is a disk
+        let disk = RoundedBox::new(1., 1., 1.).mass_data(1.);
+        assert!((disk.mass - PI).abs() < 1e-4);
+        assert!((disk.inertia - 0.5 * PI).abs() < 1e-4);
+    }
+
+    #[test]
+    fn capsule_checks() {
+        assert!(Capsule::try_new(0., 1.).is_ok());
+        assert_eq!(Capsule::try_new(1., 0.).err(), Some(ShapeError::InvalidRadius(0.)));
+        assert_eq!(Capsule::try_new(-1., 1.).err(), Some(ShapeError::InvalidExtent(-1.)));
+        assert!(Capsule::try_new(1., ::std::f32::NAN).is_err());
+        assert!(Capsule::try_new(::std::f32::INFINITY, 1.).is_err());
+    }
+
+    #[test]
+    fn rounded_box_radius() {
+        assert!(RoundedBox::try_new(2., 1., 1.).is_ok());
+        assert_eq!(RoundedBox::try_new(2., 1., 1.5).err(), Some(ShapeError::InvalidRadius(1.5)));
+        assert_eq!(RoundedBox::try_new(2., 1., -1.).err(), Some(ShapeError::InvalidRadius(-1.)));
+        assert!(RoundedBox::try_new(2., 1., ::std::f32::NAN).is_err());
+
+        assert_eq!(RoundedBox::try_new(0., 1., 0.).err(), Some(ShapeError::InvalidExtent(0.)));
+        assert_eq!(RoundedBox::try_new(2., -1., 0.).err(), Some(ShapeError::InvalidExtent(-1.)));
+        assert!(RoundedBox::try_new(::std::f32::INFINITY, 1., 1.).is_err());
+    }
+}
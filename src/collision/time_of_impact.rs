@@ -4,6 +4,7 @@ use common::math::Sweep;
 use collision::distance::{Proxy, RawProxy};
 
 #[repr(C)]
+#[derive(Clone)]
 #[doc(hidden)]
 pub struct RawInput {
     proxy_a: RawProxy,
@@ -39,8 +40,11 @@ impl<'a> Input<'a> {
 
     pub fn query(&self) -> Output {
         unsafe {
+            let mut raw = self.raw.clone();
+            raw.proxy_a.rebase();
+            raw.proxy_b.rebase();
             let mut out = mem::zeroed();
-            ffi::time_of_impact(&mut out, &self.raw);
+            ffi::time_of_impact(&mut out, &raw);
             out
         }
     }
@@ -270,6 +270,13 @@ impl Rot {
     pub fn angle(&self) -> f32 {
         self.sin.atan2(self.cos)
     }
+
+    /// Interpolates along the shortest arc from `self` to `other`.
+    pub fn slerp(&self, other: &Rot, t: f32) -> Rot {
+        let cos = self.cos * other.cos + self.sin * other.sin;
+        let sin = self.cos * other.sin - self.sin * other.cos;
+        Rot::from_angle(self.angle() + sin.atan2(cos) * t)
+    }
 }
 
 #[cfg(feature = "nalgebra")]
@@ -320,6 +327,13 @@ impl Transform {
             rot: Rot::identity(),
         }
     }
+
+    pub fn interpolate(&self, other: &Transform, t: f32) -> Transform {
+        Transform {
+            pos: self.pos + (other.pos - self.pos) * t,
+            rot: self.rot.slerp(&other.rot, t),
+        }
+    }
 }
 
 #[cfg(feature = "nalgebra")]
@@ -352,6 +366,21 @@ impl<'a> Mul<Vec2> for &'a Transform {
     }
 }
 
+/// Composes the transforms, `&(a * b) * v == a * (b * v)`.
+impl<'a, 'b> Mul<&'b Transform> for &'a Transform {
+    type Output = Transform;
+
+    fn mul(self, other: &'b Transform) -> Transform {
+        Transform {
+            pos: self * other.pos,
+            rot: Rot {
+                sin: self.rot.sin * other.rot.cos + self.rot.cos * other.rot.sin,
+                cos: self.rot.cos * other.rot.cos - self.rot.sin * other.rot.sin,
+            },
+        }
+    }
+}
+
 #[repr(C)]
 #[derive(Clone, Debug)]
 pub struct Sweep {
@@ -362,3 +391,46 @@ pub struct Sweep {
     pub a: f32,
     pub alpha0: f32,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::f32::consts::PI;
+
+    fn assert_rot(r: Rot, angle: f32) {
+        let expected = Rot::from_angle(angle);
+        assert!((r.sin - expected.sin).abs() < 1e-5 && (r.cos - expected.cos).abs() < 1e-5,
+                "{:?} is not at {}",
+                r,
+                angle);
+    }
+
+    #[test]
+    fn slerp_endpoints() {
+        let a = Rot::from_angle(0.5);
+        let b = Rot::from_angle(2.);
+        assert_rot(a.slerp(&b, 0.), 0.5);
+        assert_rot(a.slerp(&b, 1.), 2.);
+        assert_rot(a.slerp(&b, 0.5), 1.25);
+
+        // the shortest arc goes through PI, not 0
+        let a = Rot::from_angle(PI - 0.25);
+        let b = Rot::from_angle(-PI + 0.25);
+        assert_rot(a.slerp(&b, 0.), PI - 0.25);
+        assert_rot(a.slerp(&b, 0.5), PI);
+        assert_rot(a.slerp(&b, 1.), -PI + 0.25);
+    }
+
+    #[test]
+    fn interpolate_endpoints() {
+        let a = Transform { pos: Vec2 { x: 1., y: 2. }, rot: Rot::from_angle(0.) };
+        let b = Transform { pos: Vec2 { x: 3., y: -2. }, rot: Rot::from_angle(1.) };
+        assert_eq!(a.interpolate(&b, 0.).pos, a.pos);
+        assert_eq!(a.interpolate(&b, 1.).pos, b.pos);
+        assert_rot(a.interpolate(&b, 1.).rot, 1.);
+
+        let mid = a.interpolate(&b, 0.5);
+        assert_eq!(mid.pos, Vec2 { x: 2., y: 0. });
+        assert_rot(mid.rot, 0.5);
+    }
+}
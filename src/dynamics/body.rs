@@ -5,9 +5,12 @@ use std::cell::{Ref, RefMut};
 use std::marker::PhantomData;
 use wrap::*;
 use handle::*;
+use error::Error;
 use common::math::{Vec2, Transform};
-use collision::shapes::{MassData, Shape};
-use dynamics::world::{BodyHandle, JointHandle};
+use collision::shapes::{MassData, Shape, CompoundShape};
+use collision::shapes::decompose::decompose;
+use collision::shapes::rounded::ShapeGroup;
+use dynamics::world::{BodyHandle, JointHandle, WorldLocked};
 use dynamics::joints::JointEdge;
 use dynamics::fixture::{Fixture, MetaFixture, FixtureDef};
 use dynamics::contacts::{ContactEdge, Contact};
@@ -65,10 +68,27 @@ impl BodyDef {
 
 pub type FixtureHandle = TypedHandle<Fixture>;
 
+/// Fixtures created together, see `MetaBody::create_fixture_group`.
+#[derive(Clone, Debug)]
+pub struct FixtureGroup {
+    pub body: BodyHandle,
+    pub fixtures: Vec<FixtureHandle>,
+}
+
 pub struct MetaBody<U: UserDataTypes> {
     body: Body,
     fixtures: HandleMap<MetaFixture<U>, Fixture>,
     user_data: Box<InternalUserData<Body, U::BodyData>>,
+    previous_transform: Option<Transform>,
+    mass_corrections: Vec<MassCorrection>,
+}
+
+/// Replaces the summed masses of the overlapping shapes of a fixture group
+/// by the mass of their union.
+struct MassCorrection {
+    fixtures: Vec<FixtureHandle>,
+    parts: MassData,
+    group: MassData,
 }
 
 impl<U: UserDataTypes> MetaBody<U> {
@@ -81,6 +101,8 @@ impl<U: UserDataTypes> MetaBody<U> {
                 handle: handle,
                 custom: custom,
             }),
+            previous_transform: None,
+            mass_corrections: Vec::new(),
         };
         b.mut_ptr().set_internal_user_data(&mut *b.user_data);
         b
@@ -97,11 +119,33 @@ impl<U: UserDataTypes> MetaBody<U> {
                                def: &mut FixtureDef,
                                data: U::FixtureData)
                                -> FixtureHandle {
-        unsafe {
+        self.try_create_fixture_with(shape, def, data).expect("world is locked")
+    }
+
+    pub fn try_create_fixture(&mut self,
+                              shape: &dyn Shape,
+                              def: &mut FixtureDef)
+                              -> Result<FixtureHandle, WorldLocked>
+        where U::FixtureData: Default
+    {
+        self.try_create_fixture_with(shape, def, U::FixtureData::default())
+    }
+
+    pub fn try_create_fixture_with(&mut self,
+                                   shape: &dyn Shape,
+                                   def: &mut FixtureDef,
+                                   data: U::FixtureData)
+                                   -> Result<FixtureHandle, WorldLocked> {
+        self.check_unlocked()?;
+        let handle = unsafe {
             def.shape = shape.base_ptr();
             let fixture = ffi::Body_create_fixture(self.mut_ptr(), def);
             self.fixtures.insert_with(|h| MetaFixture::new(fixture, h, data))
+        };
+        if def.density > 0. {
+            self.correct_mass_data();
         }
+        Ok(handle)
     }
 
     pub fn create_fast_fixture(&mut self, shape: &dyn Shape, density: f32) -> FixtureHandle
@@ -115,10 +159,211 @@ impl<U: UserDataTypes> MetaBody<U> {
                                     density: f32,
                                     data: U::FixtureData)
                                     -> FixtureHandle {
-        unsafe {
+        self.try_create_fast_fixture_with(shape, density, data).expect("world is locked")
+    }
+
+    pub fn try_create_fast_fixture(&mut self,
+                                   shape: &dyn Shape,
+                                   density: f32)
+                                   -> Result<FixtureHandle, WorldLocked>
+        where U::FixtureData: Default
+    {
+        self.try_create_fast_fixture_with(shape, density, U::FixtureData::default())
+    }
+
+    pub fn try_create_fast_fixture_with(&mut self,
+                                        shape: &dyn Shape,
+                                        density: f32,
+                                        data: U::FixtureData)
+                                        -> Result<FixtureHandle, WorldLocked> {
+        self.check_unlocked()?;
+        let handle = unsafe {
             let fixture = ffi::Body_create_fast_fixture(self.mut_ptr(), shape.base_ptr(), density);
             self.fixtures.insert_with(|h| MetaFixture::new(fixture, h, data))
+        };
+        if density > 0. {
+            self.correct_mass_data();
         }
+        Ok(handle)
+    }
+
+    /// Splits the simple polygon `vertices` into convex polygons and attaches
+    /// one fixture per polygon, see `collision::shapes::decompose`.
+    pub fn create_concave_fixture(&mut self,
+                                  vertices: &[Vec2],
+                                  def: &FixtureDef)
+                                  -> Vec<FixtureHandle>
+        where U::FixtureData: Default + Clone
+    {
+        self.create_concave_fixture_with(vertices, def, U::FixtureData::default())
+    }
+
+    /// Every fixture gets a clone of `data`.
+    pub fn create_concave_fixture_with(&mut self,
+                                       vertices: &[Vec2],
+                                       def: &FixtureDef,
+                                       data: U::FixtureData)
+                                       -> Vec<FixtureHandle>
+        where U::FixtureData: Clone
+    {
+        self.try_create_concave_fixture_with(vertices, def, data)
+            .expect("concave fixture creation failed")
+    }
+
+    pub fn try_create_concave_fixture(&mut self,
+                                      vertices: &[Vec2],
+                                      def: &FixtureDef)
+                                      -> Result<Vec<FixtureHandle>, Error>
+        where U::FixtureData: Default + Clone
+    {
+        self.try_create_concave_fixture_with(vertices, def, U::FixtureData::default())
+    }
+
+    pub fn try_create_concave_fixture_with(&mut self,
+                                           vertices: &[Vec2],
+                                           def: &FixtureDef,
+                                           data: U::FixtureData)
+                                           -> Result<Vec<FixtureHandle>, Error>
+        where U::FixtureData: Clone
+    {
+        self.check_unlocked()?;
+        let polygons = decompose(vertices, &[])?;
+        let mut def = def.clone();
+        polygons.iter()
+            .map(|polygon| Ok(self.try_create_fixture_with(polygon, &mut def, data.clone())?))
+            .collect()
+    }
+
+    /// Attaches every part of `compound` with its own density,
+    /// the body origin being the origin of the compound.
+    pub fn create_compound_fixture(&mut self,
+                                   compound: &CompoundShape,
+                                   def: &FixtureDef)
+                                   -> Vec<FixtureHandle>
+        where U::FixtureData: Default + Clone
+    {
+        self.create_compound_fixture_with(compound, def, U::FixtureData::default())
+    }
+
+    pub fn create_compound_fixture_with(&mut self,
+                                        compound: &CompoundShape,
+                                        def: &FixtureDef,
+                                        data: U::FixtureData)
+                                        -> Vec<FixtureHandle>
+        where U::FixtureData: Clone
+    {
+        self.try_create_compound_fixture_with(compound, def, data).expect("world is locked")
+    }
+
+    pub fn try_create_compound_fixture(&mut self,
+                                       compound: &CompoundShape,
+                                       def: &FixtureDef)
+                                       -> Result<Vec<FixtureHandle>, WorldLocked>
+        where U::FixtureData: Default + Clone
+    {
+        self.try_create_compound_fixture_with(compound, def, U::FixtureData::default())
+    }
+
+    pub fn try_create_compound_fixture_with(&mut self,
+                                            compound: &CompoundShape,
+                                            def: &FixtureDef,
+                                            data: U::FixtureData)
+                                            -> Result<Vec<FixtureHandle>, WorldLocked>
+        where U::FixtureData: Clone
+    {
+        self.check_unlocked()?;
+        let mut def = def.clone();
+        compound.placed_shapes()
+            .iter()
+            .zip(compound.parts())
+            .map(|(shape, &(_, _, density))| {
+                def.density = density;
+                self.try_create_fixture_with(shape, &mut def, data.clone())
+            })
+            .collect()
+    }
+
+    /// Attaches the shapes of `group`, then corrects the mass of the body
+    /// for their overlap. The correction, computed with the density of `def`,
+    /// is applied again whenever Box2D resets the mass data of the body,
+    /// until a fixture of the group is destroyed.
+    pub fn create_fixture_group<G: ShapeGroup>(&mut self,
+                                               group: &G,
+                                               def: &FixtureDef)
+                                               -> FixtureGroup
+        where U::FixtureData: Default + Clone
+    {
+        self.create_fixture_group_with(group, def, U::FixtureData::default())
+    }
+
+    pub fn create_fixture_group_with<G: ShapeGroup>(&mut self,
+                                                    group: &G,
+                                                    def: &FixtureDef,
+                                                    data: U::FixtureData)
+                                                    -> FixtureGroup
+        where U::FixtureData: Clone
+    {
+        self.try_create_fixture_group_with(group, def, data).expect("world is locked")
+    }
+
+    pub fn try_create_fixture_group<G: ShapeGroup>(&mut self,
+                                                   group: &G,
+                                                   def: &FixtureDef)
+                                                   -> Result<FixtureGroup, WorldLocked>
+        where U::FixtureData: Default + Clone
+    {
+        self.try_create_fixture_group_with(group, def, U::FixtureData::default())
+    }
+
+    pub fn try_create_fixture_group_with<G: ShapeGroup>(&mut self,
+                                                        group: &G,
+                                                        def: &FixtureDef,
+                                                        data: U::FixtureData)
+                                                        -> Result<FixtureGroup, WorldLocked>
+        where U::FixtureData: Clone
+    {
+        self.check_unlocked()?;
+        let shapes = group.shapes();
+        let mut def = def.clone();
+        let fixtures = shapes.iter()
+            .map(|shape| self.try_create_fixture_with(shape, &mut def, data.clone()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if def.density > 0. {
+            // Box2D sums the masses of the overlapping shapes
+            let zero = MassData {
+                mass: 0.,
+                center: Vec2 { x: 0., y: 0. },
+                inertia: 0.,
+            };
+            let parts = shapes.iter()
+                .fold(zero, |sum, shape| add_mass(&sum, &shape.compute_mass(def.density), 1.));
+            self.mass_corrections.push(MassCorrection {
+                fixtures: fixtures.clone(),
+                parts: parts,
+                group: group.mass_data(def.density),
+            });
+            self.correct_mass_data();
+        }
+
+        Ok(FixtureGroup {
+            body: self.handle(),
+            fixtures: fixtures,
+        })
+    }
+
+    pub fn destroy_fixture_group(&mut self, group: FixtureGroup) {
+        self.try_destroy_fixture_group(group).expect("fixture group destruction failed")
+    }
+
+    pub fn try_destroy_fixture_group(&mut self, group: FixtureGroup) -> Result<(), Error> {
+        if group.body != self.handle() {
+            return Err(Error::InvalidHandle);
+        }
+        for fixture in group.fixtures {
+            self.try_destroy_fixture(fixture)?;
+        }
+        Ok(())
     }
 
     pub fn fixture(&self, handle: FixtureHandle) -> Ref<MetaFixture<U>> {
@@ -129,25 +374,120 @@ impl<U: UserDataTypes> MetaBody<U> {
         self.fixtures.get_mut(handle).expect("invalid fixture handle")
     }
 
-    pub fn try_fixture(&self, handle: FixtureHandle) -> Option<Ref<MetaFixture<U>>> {
-        self.fixtures.get(handle)
+    pub fn try_fixture(&self, handle: FixtureHandle) -> Result<Ref<MetaFixture<U>>, Error> {
+        self.fixtures.try_get(handle)
     }
 
-    pub fn try_fixture_mut(&self, handle: FixtureHandle) -> Option<RefMut<MetaFixture<U>>> {
-        self.fixtures.get_mut(handle)
+    pub fn try_fixture_mut(&self, handle: FixtureHandle) -> Result<RefMut<MetaFixture<U>>, Error> {
+        self.fixtures.try_get_mut(handle)
     }
 
     pub fn destroy_fixture(&mut self, handle: FixtureHandle) {
-        let mut fixture = self.fixtures.remove(handle);
+        self.try_destroy_fixture(handle).expect("fixture destruction failed")
+    }
+
+    pub fn try_destroy_fixture(&mut self, handle: FixtureHandle) -> Result<(), Error> {
+        self.check_unlocked()?;
+        let mut fixture = self.fixtures.try_remove(handle)?;
+        self.mass_corrections.retain(|correction| !correction.fixtures.contains(&handle));
         unsafe {
             ffi::Body_destroy_fixture(self.mut_ptr(), fixture.mut_ptr());
         }
+        self.correct_mass_data();
+        Ok(())
+    }
+
+    pub fn reset_mass_data(&mut self) {
+        self.body.reset_mass_data();
+        self.correct_mass_data();
+    }
+
+    pub fn set_body_type(&mut self, typ: BodyType) {
+        self.try_set_body_type(typ).expect("world is locked")
+    }
+
+    pub fn try_set_body_type(&mut self, typ: BodyType) -> Result<(), WorldLocked> {
+        if typ != self.body_type() {
+            self.body.try_set_body_type(typ)?;
+            self.correct_mass_data();
+        }
+        Ok(())
+    }
+
+    pub fn set_rotation_fixed(&mut self, flag: bool) {
+        if flag != self.is_rotation_fixed() {
+            self.body.set_rotation_fixed(flag);
+            self.correct_mass_data();
+        }
+    }
+
+    /// Called where Box2D resets the mass data, to apply the corrections of
+    /// the fixture groups again. Box2D cannot set mass data while the world
+    /// is locked.
+    fn correct_mass_data(&mut self) {
+        if self.mass_corrections.is_empty() || self.body_type() != BodyType::Dynamic ||
+           self.check_unlocked().is_err() {
+            return;
+        }
+        self.body.reset_mass_data();
+        let mut mass_data = self.mass_data();
+        for correction in &self.mass_corrections {
+            mass_data = add_mass(&mass_data, &correction.parts, -1.);
+            mass_data = add_mass(&mass_data, &correction.group, 1.);
+        }
+        unsafe { ffi::Body_set_mass_data(self.body.mut_ptr(), &mass_data) }
     }
 
     pub fn fixtures(&self) -> HandleIter<Fixture, MetaFixture<U>> {
         self.fixtures.iter()
     }
 
+    /// The next fixture created gets `handle`, whose slot must be free.
+    #[doc(hidden)]
+    pub fn reserve_next_fixture(&mut self, handle: FixtureHandle) -> Result<(), Error> {
+        self.fixtures.reserve_next(handle)
+    }
+
+    #[doc(hidden)]
+    pub fn fixture_handle_layout(&self) -> HandleLayout {
+        self.fixtures.layout()
+    }
+
+    #[doc(hidden)]
+    pub fn set_fixture_handle_layout(&mut self, layout: &HandleLayout) -> Result<(), Error> {
+        self.fixtures.set_layout(layout)
+    }
+
+    #[doc(hidden)]
+    pub fn into_fixtures(self) -> HandleIntoIter<Fixture, MetaFixture<U>> {
+        self.fixtures.into_iter()
+    }
+
+    /// The transform before the last `World::step`, only recorded
+    /// when the world is recording transforms.
+    pub fn previous_transform(&self) -> Option<&Transform> {
+        self.previous_transform.as_ref()
+    }
+
+    /// Interpolates between the previous and the current transform,
+    /// falls back to the current transform if none was recorded.
+    pub fn interpolated_transform(&self, alpha: f32) -> Transform {
+        match self.previous_transform {
+            Some(ref previous) => previous.interpolate(self.transform(), alpha),
+            None => self.transform().clone(),
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn record_transform(&mut self) {
+        self.previous_transform = Some(self.transform().clone());
+    }
+
+    #[doc(hidden)]
+    pub fn forget_transform(&mut self) {
+        self.previous_transform = None;
+    }
+
     /// This method is here because contacts are owned by the world and not by the body,
     /// and having a reference to a `MetaBody` requires having a reference to the world.
     pub fn contacts(&self) -> ContactIter {
@@ -168,6 +508,19 @@ impl<U: UserDataTypes> MetaBody<U> {
     }
 }
 
+fn add_mass(a: &MassData, b: &MassData, sign: f32) -> MassData {
+    let mass = a.mass + sign * b.mass;
+    MassData {
+        mass: mass,
+        center: if mass > 0. {
+            (a.center * a.mass + b.center * (sign * b.mass)) / mass
+        } else {
+            a.center
+        },
+        inertia: a.inertia + sign * b.inertia,
+    }
+}
+
 impl<U: UserDataTypes> UserData<U::BodyData> for MetaBody<U> {
     fn user_data(&self) -> &U::BodyData {
         &self.user_data.custom
@@ -320,8 +673,22 @@ impl Body {
         }
     }
 
+    pub fn is_world_locked(&self) -> bool {
+        unsafe { ffi::World_is_locked(ffi::Body_get_world_const(self.ptr())) }
+    }
+
+    fn check_unlocked(&self) -> Result<(), WorldLocked> {
+        if self.is_world_locked() { Err(WorldLocked) } else { Ok(()) }
+    }
+
     pub fn set_transform(&mut self, pos: &Vec2, angle: f32) {
+        self.try_set_transform(pos, angle).expect("world is locked")
+    }
+
+    pub fn try_set_transform(&mut self, pos: &Vec2, angle: f32) -> Result<(), WorldLocked> {
+        self.check_unlocked()?;
         unsafe { ffi::Body_set_transform(self.mut_ptr(), pos, angle) }
+        Ok(())
     }
 
     pub fn set_linear_velocity(&mut self, v: &Vec2) {
@@ -353,7 +720,13 @@ impl Body {
     }
 
     pub fn set_mass_data(&mut self, data: &MassData) {
+        self.try_set_mass_data(data).expect("world is locked")
+    }
+
+    pub fn try_set_mass_data(&mut self, data: &MassData) -> Result<(), WorldLocked> {
+        self.check_unlocked()?;
         unsafe { ffi::Body_set_mass_data(self.mut_ptr(), data) }
+        Ok(())
     }
 
     pub fn reset_mass_data(&mut self) {
@@ -373,7 +746,13 @@ impl Body {
     }
 
     pub fn set_body_type(&mut self, typ: BodyType) {
+        self.try_set_body_type(typ).expect("world is locked")
+    }
+
+    pub fn try_set_body_type(&mut self, typ: BodyType) -> Result<(), WorldLocked> {
+        self.check_unlocked()?;
         unsafe { ffi::Body_set_type(self.mut_ptr(), typ) }
+        Ok(())
     }
 
     pub fn set_bullet(&mut self, flag: bool) {
@@ -389,7 +768,13 @@ impl Body {
     }
 
     pub fn set_active(&mut self, flag: bool) {
+        self.try_set_active(flag).expect("world is locked")
+    }
+
+    pub fn try_set_active(&mut self, flag: bool) -> Result<(), WorldLocked> {
+        self.check_unlocked()?;
         unsafe { ffi::Body_set_active(self.mut_ptr(), flag) }
+        Ok(())
     }
 
     pub fn set_rotation_fixed(&mut self, flag: bool) {
@@ -463,6 +848,7 @@ pub mod ffi {
     pub use ffi::Any;
     pub use collision::shapes::ffi::Shape;
     pub use dynamics::fixture::ffi::Fixture;
+    pub use dynamics::world::ffi::{World, World_is_locked};
     use common::math::{Vec2, Transform};
     use collision::shapes::MassData;
     use dynamics::fixture::FixtureDef;
@@ -534,15 +920,15 @@ pub mod ffi {
         pub fn Body_set_fixed_rotation(slf: *mut Body, flag: bool);
         pub fn Body_is_fixed_rotation(slf: *const Body) -> bool;
         // pub fn Body_get_fixture_list(slf: *mut Body) -> *mut Fixture;
-        // pub fn Body_get_fixture_list_const(slf: *const Body) -> *const Fixture;
+        pub fn Body_get_fixture_list_const(slf: *const Body) -> *const Fixture;
         pub fn Body_get_joint_list(slf: *mut Body) -> *mut JointEdge;
         pub fn Body_get_joint_list_const(slf: *const Body) -> *const JointEdge;
         pub fn Body_get_contact_list(slf: *mut Body) -> *mut ContactEdge;
         pub fn Body_get_contact_list_const(slf: *const Body) -> *const ContactEdge;
         // pub fn Body_get_next(slf: *mut Body) -> *mut Body;
-        // pub fn Body_get_next_const(slf: *const Body) -> *const Body;
+        pub fn Body_get_next_const(slf: *const Body) -> *const Body;
         // pub fn Body_get_world(slf: *mut Body) -> *mut World;
-        // pub fn Body_get_world_const(slf: *const Body) -> *const World;
+        pub fn Body_get_world_const(slf: *const Body) -> *const World;
         pub fn Body_dump(slf: *mut Body);
     }
 }
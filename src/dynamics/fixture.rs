@@ -1,10 +1,11 @@
 use std::mem;
 use std::ptr;
+use std::convert::TryFrom;
 use std::ops::{Deref, DerefMut};
 use wrap::*;
 use common::math::Vec2;
 use collision::{AABB, RayCastInput, RayCastOutput};
-use collision::shapes::{MassData, ShapeType, UnknownShape};
+use collision::shapes::{MassData, ShapeDesc, ShapeType, UnknownShape};
 use dynamics::world::BodyHandle;
 use dynamics::body::FixtureHandle;
 use user_data::{UserDataTypes, UserData, RawUserData, RawUserDataMut, InternalUserData};
@@ -26,6 +27,14 @@ impl Filter {
             group_index: 0,
         }
     }
+
+    /// Same rule as Box2D's default contact filter.
+    pub fn should_collide(&self, other: &Filter) -> bool {
+        if self.group_index == other.group_index && self.group_index != 0 {
+            return self.group_index > 0;
+        }
+        (self.mask_bits & other.category_bits) != 0 && (self.category_bits & other.mask_bits) != 0
+    }
 }
 
 #[repr(C)]
@@ -77,6 +86,12 @@ impl<U: UserDataTypes> MetaFixture<U> {
         f.mut_ptr().set_internal_user_data(&mut *f.user_data);
         f
     }
+
+    #[doc(hidden)]
+    pub fn into_user_data(self) -> U::FixtureData {
+        let InternalUserData { custom, .. } = *self.user_data;
+        custom
+    }
 }
 
 impl<U: UserDataTypes> UserData<U::FixtureData> for MetaFixture<U> {
@@ -180,6 +195,13 @@ impl Fixture {
         }
     }
 
+    /// A copy of the shape that is not tied to the fixture.
+    pub fn shape_desc(&self) -> ShapeDesc {
+        let shape = self.shape();
+        // fixtures always have one of the Box2D shape types
+        ShapeDesc::try_from(&*shape).expect("truly unknown shape")
+    }
+
     pub fn set_sensor(&mut self, flag: bool) {
         unsafe { ffi::Fixture_set_sensor(self.mut_ptr(), flag) }
     }
@@ -233,7 +255,7 @@ pub mod ffi {
         pub fn Fixture_get_body(slf: *mut Fixture) -> *mut Body;
         pub fn Fixture_get_body_const(slf: *const Fixture) -> *const Body;
         // pub fn Fixture_get_next(slf: *mut Fixture) -> *mut Fixture;
-        // pub fn Fixture_get_next_const(slf: *const Fixture) -> *const Fixture;
+        pub fn Fixture_get_next_const(slf: *const Fixture) -> *const Fixture;
         pub fn Fixture_test_point(slf: *const Fixture, p: *const Vec2) -> bool;
         pub fn Fixture_ray_cast(slf: *const Fixture,
                                 output: *mut RayCastOutput,
@@ -1,8 +1,10 @@
 use wrap::*;
 use common::math::Vec2;
 use user_data::UserDataTypes;
+use error::Error;
 use dynamics::world::{World, BodyHandle};
-use dynamics::joints::{Joint, JointType, JointDef};
+use dynamics::joints::{Joint, JointType, JointDef, JointDefError,
+                       check_range, validate_bodies};
 
 pub struct DistanceJointDef {
     pub body_a: BodyHandle,
@@ -43,7 +45,7 @@ impl DistanceJointDef {
                                       body_a: BodyHandle,
                                       body_b: BodyHandle,
                                       anchor_a: &Vec2,
-                                      anchor_b: &Vec2) -> Option<()> {
+                                      anchor_b: &Vec2) -> Result<(), Error> {
         self.body_a = body_a;
         self.body_b = body_b;
         let a = world.try_body(body_a)?;
@@ -51,7 +53,7 @@ impl DistanceJointDef {
         self.local_anchor_a = a.local_point(anchor_a);
         self.local_anchor_b = b.local_point(anchor_b);
         self.length = (anchor_b - anchor_a).norm();
-        Some(())
+        Ok(())
     }
 }
 
@@ -62,12 +64,19 @@ impl JointDef for DistanceJointDef {
         JointType::Distance
     }
 
+    fn validate<U: UserDataTypes>(&self, world: &World<U>) -> Result<(), JointDefError> {
+        validate_bodies(world, self.body_a, self.body_b)?;
+        check_range("length", self.length >= 0.)?;
+        check_range("frequency", self.frequency >= 0.)?;
+        check_range("damping_ratio", self.damping_ratio >= 0.)
+    }
+
     unsafe fn create<U: UserDataTypes>(&self, world: &mut World<U>) -> *mut ffi::Joint {
         self.try_create(world).expect("joint create failed: invalid body handle")
     }
 
-    unsafe fn try_create<U: UserDataTypes>(&self, world: &mut World<U>) -> Option<*mut ffi::Joint> {
-        Some(ffi::World_create_distance_joint(world.mut_ptr(),
+    unsafe fn try_create<U: UserDataTypes>(&self, world: &mut World<U>) -> Result<*mut ffi::Joint, Error> {
+        Ok(ffi::World_create_distance_joint(world.mut_ptr(),
                                               world.try_body_mut(self.body_a)?.mut_ptr(),
                                               world.try_body_mut(self.body_b)?.mut_ptr(),
                                               self.collide_connected,
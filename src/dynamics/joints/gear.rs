@@ -1,7 +1,8 @@
 use wrap::*;
 use user_data::{UserDataTypes, RawUserData};
-use dynamics::world::{World, JointHandle};
-use dynamics::joints::{Joint, JointType, JointDef};
+use error::Error;
+use dynamics::world::{World, BodyHandle, JointHandle};
+use dynamics::joints::{Joint, JointType, JointDef, JointDefError, check_range};
 
 pub struct GearJointDef {
     pub collide_connected: bool,
@@ -28,12 +29,26 @@ impl JointDef for GearJointDef {
         JointType::Gear
     }
 
+    fn validate<U: UserDataTypes>(&self, world: &World<U>) -> Result<(), JointDefError> {
+        let (type_1, body_1) = gear_side(world, self.joint_1)?;
+        let (type_2, body_2) = gear_side(world, self.joint_2)?;
+        for &joint_type in &[type_1, type_2] {
+            if joint_type != JointType::Revolute && joint_type != JointType::Prismatic {
+                return Err(JointDefError::UnsupportedGearJoint(joint_type));
+            }
+        }
+        if body_1 == body_2 {
+            return Err(JointDefError::SameBody);
+        }
+        check_range("ratio", self.ratio.is_finite())
+    }
+
     unsafe fn create<U: UserDataTypes>(&self, world: &mut World<U>) -> *mut ffi::Joint {
         self.try_create(world).expect("joint create failed: invalid joint handle")
     }
 
-    unsafe fn try_create<U: UserDataTypes>(&self, world: &mut World<U>) -> Option<*mut ffi::Joint> {
-        Some(ffi::World_create_gear_joint(world.mut_ptr(),
+    unsafe fn try_create<U: UserDataTypes>(&self, world: &mut World<U>) -> Result<*mut ffi::Joint, Error> {
+        Ok(ffi::World_create_gear_joint(world.mut_ptr(),
                                           self.collide_connected,
                                           world.try_joint_mut(self.joint_1)?.mut_base_ptr(),
                                           world.try_joint_mut(self.joint_2)?.mut_base_ptr(),
@@ -41,6 +56,17 @@ impl JointDef for GearJointDef {
     }
 }
 
+fn gear_side<U: UserDataTypes>(world: &World<U>,
+                               joint: JointHandle)
+                               -> Result<(JointType, BodyHandle), JointDefError> {
+    let joint = world.try_joint(joint).map_err(|e| match e {
+            Error::AlreadyBorrowed => JointDefError::AlreadyBorrowed,
+            _ => JointDefError::InvalidJoint,
+        })?;
+    // the gear joint connects the second bodies of both joints
+    Ok((joint.get_type(), joint.body_b()))
+}
+
 wrap_joint! {
     ffi::GearJoint => GearJoint (JointType::Gear)
     < ffi::GearJoint_as_joint
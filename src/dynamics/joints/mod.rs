@@ -42,7 +42,10 @@ pub use self::wheel::{WheelJoint, WheelJointDef};
 
 
 use std::ops::{Deref, DerefMut};
+use std::fmt;
+use std::error;
 use wrap::*;
+use error::Error;
 use common::math::Vec2;
 use dynamics::world::{World, BodyHandle, JointHandle};
 use user_data::{UserDataTypes, UserData, RawUserData, RawUserDataMut, InternalUserData};
@@ -73,15 +76,78 @@ pub enum LimitState {
     Equal,
 }
 
+/// Why a joint definition would trip a Box2D assertion.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum JointDefError {
+    InvalidBody,
+    InvalidJoint,
+    /// A body or joint of the definition is borrowed by the caller.
+    AlreadyBorrowed,
+    /// Both sides of the joint are attached to the same body.
+    SameBody,
+    /// Gear joints only connect revolute and prismatic joints.
+    UnsupportedGearJoint(JointType),
+    /// The named parameter is out of its valid range.
+    OutOfRange(&'static str),
+}
+
+impl fmt::Display for JointDefError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            JointDefError::InvalidBody => write!(f, "invalid body handle"),
+            JointDefError::InvalidJoint => write!(f, "invalid joint handle"),
+            JointDefError::AlreadyBorrowed => write!(f, "body or joint already borrowed"),
+            JointDefError::SameBody => write!(f, "both bodies are the same"),
+            JointDefError::UnsupportedGearJoint(t) => {
+                write!(f, "gear joints cannot connect {:?} joints", t)
+            }
+            JointDefError::OutOfRange(name) => write!(f, "{} is out of range", name),
+        }
+    }
+}
+
+impl error::Error for JointDefError {}
+
 pub trait JointDef {
     fn joint_type() -> JointType where Self: Sized;
 
+    /// Checks the handles and parameters, called by `World::try_create_joint`.
+    fn validate<U: UserDataTypes>(&self, world: &World<U>) -> Result<(), JointDefError>;
+
     #[doc(hidden)]
     unsafe fn create<U: UserDataTypes>(&self, world: &mut World<U>) -> *mut ffi::Joint;
     
     #[doc(hidden)]
-    unsafe fn try_create<U: UserDataTypes>(&self, world: &mut World<U>) -> Option<*mut ffi::Joint> {
-        Some(self.create(world))
+    unsafe fn try_create<U: UserDataTypes>(&self, world: &mut World<U>) -> Result<*mut ffi::Joint, Error> {
+        Ok(self.create(world))
+    }
+}
+
+#[doc(hidden)]
+pub fn validate_bodies<U: UserDataTypes>(world: &World<U>,
+                                         body_a: BodyHandle,
+                                         body_b: BodyHandle)
+                                         -> Result<(), JointDefError> {
+    for &body in &[body_a, body_b] {
+        match world.try_body(body) {
+            Err(Error::AlreadyBorrowed) => return Err(JointDefError::AlreadyBorrowed),
+            Err(_) => return Err(JointDefError::InvalidBody),
+            Ok(_) => {}
+        }
+    }
+    if body_a == body_b {
+        Err(JointDefError::SameBody)
+    } else {
+        Ok(())
+    }
+}
+
+#[doc(hidden)]
+pub fn check_range(name: &'static str, valid: bool) -> Result<(), JointDefError> {
+    if valid {
+        Ok(())
+    } else {
+        Err(JointDefError::OutOfRange(name))
     }
 }
 
@@ -103,6 +169,12 @@ impl<U: UserDataTypes> MetaJoint<U> {
         j.mut_base_ptr().set_internal_user_data(&mut *j.user_data);
         j
     }
+
+    #[doc(hidden)]
+    pub fn into_user_data(self) -> U::JointData {
+        let InternalUserData { custom, .. } = *self.user_data;
+        custom
+    }
 }
 
 impl<U: UserDataTypes> UserData<U::JointData> for MetaJoint<U> {
@@ -292,7 +364,7 @@ pub mod ffi {
         pub fn Joint_get_reaction_force_virtual(slf: *const Joint) -> Vec2;
         pub fn Joint_get_reaction_torque_virtual(slf: *const Joint) -> f32;
         // pub fn Joint_get_next(slf: *mut Joint) -> *mut Joint;
-        // pub fn Joint_get_next_const(slf: *const Joint) -> *const Joint;
+        pub fn Joint_get_next_const(slf: *const Joint) -> *const Joint;
         pub fn Joint_is_active(slf: *const Joint) -> bool;
         pub fn Joint_get_collide_connected(slf: *const Joint) -> bool;
         pub fn Joint_dump_virtual(slf: *mut Joint);
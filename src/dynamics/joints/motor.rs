@@ -1,8 +1,10 @@
 use wrap::*;
 use common::math::Vec2;
 use user_data::UserDataTypes;
+use error::Error;
 use dynamics::world::{World, BodyHandle};
-use dynamics::joints::{Joint, JointType, JointDef};
+use dynamics::joints::{Joint, JointType, JointDef, JointDefError,
+                       check_range, validate_bodies};
 
 pub struct MotorJointDef {
     pub body_a: BodyHandle,
@@ -39,14 +41,14 @@ impl MotorJointDef {
     pub fn try_init<U: UserDataTypes>(&mut self,
                                       world: &World<U>,
                                       body_a: BodyHandle,
-                                      body_b: BodyHandle) -> Option<()> {
+                                      body_b: BodyHandle) -> Result<(), Error> {
         self.body_a = body_a;
         self.body_b = body_b;
         let a = world.try_body(body_a)?;
         let b = world.try_body(body_b)?;
         self.linear_offset = a.local_point(b.position());
         self.angular_offset = b.angle() - a.angle();
-        Some(())
+        Ok(())
     }
 }
 
@@ -57,12 +59,20 @@ impl JointDef for MotorJointDef {
         JointType::Motor
     }
 
+    fn validate<U: UserDataTypes>(&self, world: &World<U>) -> Result<(), JointDefError> {
+        validate_bodies(world, self.body_a, self.body_b)?;
+        check_range("max_force", self.max_force >= 0.)?;
+        check_range("max_torque", self.max_torque >= 0.)?;
+        check_range("correction_factor",
+                    self.correction_factor >= 0. && self.correction_factor <= 1.)
+    }
+
     unsafe fn create<U: UserDataTypes>(&self, world: &mut World<U>) -> *mut ffi::Joint {
         self.try_create(world).expect("joint create failed: invalid body handle")
     }
 
-    unsafe fn try_create<U: UserDataTypes>(&self, world: &mut World<U>) -> Option<*mut ffi::Joint> {
-        Some(ffi::World_create_motor_joint(world.mut_ptr(),
+    unsafe fn try_create<U: UserDataTypes>(&self, world: &mut World<U>) -> Result<*mut ffi::Joint, Error> {
+        Ok(ffi::World_create_motor_joint(world.mut_ptr(),
                                            world.try_body_mut(self.body_a)?.mut_ptr(),
                                            world.try_body_mut(self.body_b)?.mut_ptr(),
                                            self.collide_connected,
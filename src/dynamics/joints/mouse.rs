@@ -1,8 +1,10 @@
 use wrap::*;
 use common::math::Vec2;
 use user_data::UserDataTypes;
+use error::Error;
 use dynamics::world::{World, BodyHandle};
-use dynamics::joints::{Joint, JointType, JointDef};
+use dynamics::joints::{Joint, JointType, JointDef, JointDefError,
+                       check_range, validate_bodies};
 
 pub struct MouseJointDef {
     pub body_a: BodyHandle,
@@ -35,12 +37,20 @@ impl JointDef for MouseJointDef {
         JointType::Mouse
     }
 
+    fn validate<U: UserDataTypes>(&self, world: &World<U>) -> Result<(), JointDefError> {
+        validate_bodies(world, self.body_a, self.body_b)?;
+        check_range("target", self.target.x.is_finite() && self.target.y.is_finite())?;
+        check_range("max_force", self.max_force >= 0.)?;
+        check_range("frequency", self.frequency >= 0.)?;
+        check_range("damping_ratio", self.damping_ratio >= 0.)
+    }
+
     unsafe fn create<U: UserDataTypes>(&self, world: &mut World<U>) -> *mut ffi::Joint {
         self.try_create(world).expect("joint create failed: invalid body handle")
     }
 
-    unsafe fn try_create<U: UserDataTypes>(&self, world: &mut World<U>) -> Option<*mut ffi::Joint> {
-        Some(ffi::World_create_mouse_joint(world.mut_ptr(),
+    unsafe fn try_create<U: UserDataTypes>(&self, world: &mut World<U>) -> Result<*mut ffi::Joint, Error> {
+        Ok(ffi::World_create_mouse_joint(world.mut_ptr(),
                                            world.try_body_mut(self.body_a)?.mut_ptr(),
                                            world.try_body_mut(self.body_b)?.mut_ptr(),
                                            self.collide_connected,
@@ -1,8 +1,10 @@
 use wrap::*;
 use common::math::Vec2;
 use user_data::UserDataTypes;
+use error::Error;
 use dynamics::world::{World, BodyHandle};
-use dynamics::joints::{Joint, JointType, JointDef};
+use dynamics::joints::{Joint, JointType, JointDef, JointDefError,
+                       check_range, validate_bodies};
 
 pub struct PulleyJointDef {
     pub body_a: BodyHandle,
@@ -59,12 +61,19 @@ impl JointDef for PulleyJointDef {
         JointType::Pulley
     }
 
+    fn validate<U: UserDataTypes>(&self, world: &World<U>) -> Result<(), JointDefError> {
+        validate_bodies(world, self.body_a, self.body_b)?;
+        check_range("length_a", self.length_a >= 0.)?;
+        check_range("length_b", self.length_b >= 0.)?;
+        check_range("ratio", self.ratio > ::std::f32::EPSILON)
+    }
+
     unsafe fn create<U: UserDataTypes>(&self, world: &mut World<U>) -> *mut ffi::Joint {
         self.try_create(world).expect("joint create failed: invalid body handle")
     }
 
-    unsafe fn try_create<U: UserDataTypes>(&self, world: &mut World<U>) -> Option<*mut ffi::Joint> {
-        Some(ffi::World_create_pulley_joint(world.mut_ptr(),
+    unsafe fn try_create<U: UserDataTypes>(&self, world: &mut World<U>) -> Result<*mut ffi::Joint, Error> {
+        Ok(ffi::World_create_pulley_joint(world.mut_ptr(),
                                             world.try_body_mut(self.body_a)?.mut_ptr(),
                                             world.try_body_mut(self.body_b)?.mut_ptr(),
                                             self.collide_connected,
@@ -1,8 +1,10 @@
 use wrap::*;
 use common::math::Vec2;
 use user_data::UserDataTypes;
+use error::Error;
 use dynamics::world::{World, BodyHandle};
-use dynamics::joints::{Joint, JointType, JointDef};
+use dynamics::joints::{Joint, JointType, JointDef, JointDefError,
+                       check_range, validate_bodies};
 
 pub struct WheelJointDef {
     pub body_a: BodyHandle,
@@ -49,7 +51,7 @@ impl WheelJointDef {
                                       body_a: BodyHandle,
                                       body_b: BodyHandle,
                                       anchor: &Vec2,
-                                      axis: &Vec2) -> Option<()> {
+                                      axis: &Vec2) -> Result<(), Error> {
         self.body_a = body_a;
         self.body_b = body_b;
         let a = world.try_body(body_a)?;
@@ -57,7 +59,7 @@ impl WheelJointDef {
         self.local_anchor_a = a.local_point(anchor);
         self.local_anchor_b = b.local_point(anchor);
         self.local_axis_a = a.local_vector(axis);
-        Some(())
+        Ok(())
     }
 }
 
@@ -68,12 +70,20 @@ impl JointDef for WheelJointDef {
         JointType::Wheel
     }
 
+    fn validate<U: UserDataTypes>(&self, world: &World<U>) -> Result<(), JointDefError> {
+        validate_bodies(world, self.body_a, self.body_b)?;
+        check_range("local_axis_a", self.local_axis_a.norm() > ::std::f32::EPSILON)?;
+        check_range("max_motor_torque", self.max_motor_torque >= 0.)?;
+        check_range("frequency", self.frequency >= 0.)?;
+        check_range("damping_ratio", self.damping_ratio >= 0.)
+    }
+
     unsafe fn create<U: UserDataTypes>(&self, world: &mut World<U>) -> *mut ffi::Joint {
         self.try_create(world).expect("joint create failed: invalid body handle")
     }
 
-    unsafe fn try_create<U: UserDataTypes>(&self, world: &mut World<U>) -> Option<*mut ffi::Joint> {
-        Some(ffi::World_create_wheel_joint(world.mut_ptr(),
+    unsafe fn try_create<U: UserDataTypes>(&self, world: &mut World<U>) -> Result<*mut ffi::Joint, Error> {
+        Ok(ffi::World_create_wheel_joint(world.mut_ptr(),
                                            world.try_body_mut(self.body_a)?.mut_ptr(),
                                            world.try_body_mut(self.body_b)?.mut_ptr(),
                                            self.collide_connected,
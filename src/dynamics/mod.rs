@@ -3,6 +3,7 @@ pub mod body;
 pub mod fixture;
 pub mod joints;
 pub mod contacts;
+pub mod stepper;
 
 #[repr(C)]
 #[derive(Clone)]
@@ -0,0 +1,89 @@
+use common::math::Transform;
+use user_data::UserDataTypes;
+use dynamics::world::{World, BodyHandle};
+
+/// Drives a `World` with a fixed time step from variable frame times.
+///
+/// Forces applied between two updates act on every sub-step, they are
+/// cleared once all the sub-steps of an update have been taken if
+/// `auto_clear_forces` is enabled on the world.
+pub struct Stepper {
+    time_step: f32,
+    pub max_substeps: u32,
+    /// Makes `update` enable `World::set_recording_transforms`,
+    /// for `interpolated_transform`. Disabled by default.
+    pub record_transforms: bool,
+    pub velocity_iterations: i32,
+    pub position_iterations: i32,
+    accumulator: f32,
+}
+
+impl Stepper {
+    pub fn new(time_step: f32) -> Stepper {
+        assert!(time_step > 0., "time step must be positive");
+        Stepper {
+            time_step: time_step,
+            max_substeps: 5,
+            record_transforms: false,
+            velocity_iterations: 8,
+            position_iterations: 3,
+            accumulator: 0.,
+        }
+    }
+
+    pub fn time_step(&self) -> f32 {
+        self.time_step
+    }
+
+    pub fn set_time_step(&mut self, time_step: f32) {
+        assert!(time_step > 0., "time step must be positive");
+        self.time_step = time_step;
+    }
+
+    /// Advances the world by `dt`, returns the number of steps taken.
+    /// A negative `dt` counts as 0.
+    pub fn update<U: UserDataTypes>(&mut self, world: &mut World<U>, dt: f32) -> u32 {
+        self.accumulator += dt.max(0.);
+        if self.record_transforms && !world.is_recording_transforms() {
+            world.set_recording_transforms(true);
+        }
+
+        let auto_clear_forces = world.is_auto_clearing_forces();
+        world.set_auto_clearing_forces(false);
+        let mut steps = 0;
+        while self.accumulator >= self.time_step && steps < self.max_substeps {
+            world.step(self.time_step, self.velocity_iterations, self.position_iterations);
+            self.accumulator -= self.time_step;
+            steps += 1;
+        }
+        world.set_auto_clearing_forces(auto_clear_forces);
+
+        if self.accumulator >= self.time_step {
+            // too far behind, drop the time we could not simulate
+            self.accumulator %= self.time_step;
+        }
+
+        if steps > 0 && auto_clear_forces {
+            world.clear_forces();
+        }
+        steps
+    }
+
+    /// How far between the previous and the current transforms
+    /// the rendering time is, in `[0, 1)`.
+    pub fn alpha(&self) -> f32 {
+        self.accumulator / self.time_step
+    }
+
+    /// The transform of the body at the rendering time,
+    /// see `MetaBody::interpolated_transform`.
+    pub fn interpolated_transform<U>(&self, world: &World<U>, body: BodyHandle) -> Option<Transform>
+        where U: UserDataTypes
+    {
+        world.try_body(body).ok().map(|body| body.interpolated_transform(self.alpha()))
+    }
+
+    pub fn reset(&mut self) {
+        self.accumulator = 0.;
+    }
+}
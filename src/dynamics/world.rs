@@ -1,12 +1,21 @@
 #[path = "world_callbacks.rs"]
 pub mod callbacks;
+#[path = "world_queries.rs"]
+pub mod queries;
+#[path = "world_commands.rs"]
+pub mod commands;
+#[path = "world_state.rs"]
+pub mod state;
 
 use std::mem;
 use std::ptr;
+use std::fmt;
+use std::error;
 use std::marker::PhantomData;
 use std::cell::{Ref, RefMut};
 use wrap::*;
 use handle::*;
+use error::Error;
 use common::{Draw, DrawLink, DrawFlags};
 use common::math::Vec2;
 use collision::AABB;
@@ -15,20 +24,37 @@ use user_data::UserDataTypes;
 use dynamics::body::{BodyDef, MetaBody, Body};
 use dynamics::joints::{Joint, JointDef, MetaJoint};
 use dynamics::contacts::Contact;
-use self::callbacks::{ContactFilter, ContactFilterLink,
+use self::callbacks::{DestructionListener, ContactEvent, ContactEventQueue,
+                      ContactFilter, ContactFilterLink,
                       ContactListener, ContactListenerLink,
                       QueryCallback, QueryCallbackLink,
                       RayCastCallback, RayCastCallbackLink};
+use self::commands::WorldCommands;
 
 pub type BodyHandle = TypedHandle<Body>;
 pub type JointHandle = TypedHandle<dyn Joint>;
 
+/// The world is locked during a step, it cannot be modified from callbacks.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct WorldLocked;
+
+impl fmt::Display for WorldLocked {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the world is locked")
+    }
+}
+
+impl error::Error for WorldLocked {}
+
 pub struct World<U: UserDataTypes> {
     ptr: *mut ffi::World,
     bodies: HandleMap<MetaBody<U>, Body>,
     joints: HandleMap<MetaJoint<U>, dyn Joint>,
-    contact_filter_link: ContactFilterLink,
-    contact_listener_link: ContactListenerLink,
+    destruction_listener: Option<Box<dyn DestructionListener<U>>>,
+    recording_transforms: bool,
+    commands: WorldCommands<U>,
+    contact_filter_link: ContactFilterLink<U>,
+    contact_listener_link: ContactListenerLink<U>,
     draw_link: DrawLink,
 }
 
@@ -45,17 +71,27 @@ impl<U: UserDataTypes> Wrapped<ffi::World> for World<U> {
 
 impl<U: UserDataTypes> World<U> {
     pub fn new(gravity: &Vec2) -> Self {
+        let commands = WorldCommands::new();
         unsafe {
             World {
                 ptr: ffi::World_new(gravity),
                 bodies: HandleMap::new(),
                 joints: HandleMap::new(),
-                contact_filter_link: ContactFilterLink::new(),
-                contact_listener_link: ContactListenerLink::new(),
+                destruction_listener: None,
+                recording_transforms: false,
+                commands: commands.clone(),
+                contact_filter_link: ContactFilterLink::new(commands.clone()),
+                contact_listener_link: ContactListenerLink::new(commands),
                 draw_link: DrawLink::new(),
             }
         }
     }
+
+    /// The listener is called by `destroy_body` for each joint and fixture
+    /// that was attached to the destroyed body.
+    pub fn set_destruction_listener<L: DestructionListener<U> + 'static>(&mut self, listener: Box<L>) {
+        self.destruction_listener = Some(listener);
+    }
         
     pub fn set_contact_filter<F: ContactFilter<U>>(&mut self, filter: Box<F>) {
         unsafe {
@@ -71,6 +107,14 @@ impl<U: UserDataTypes> World<U> {
         }
     }
 
+    /// Takes the events recorded since the last call if the contact listener
+    /// is a `ContactEventQueue`, returns nothing otherwise.
+    pub fn drain_contact_events(&mut self) -> Vec<ContactEvent> {
+        self.contact_listener_link
+            .listener_mut::<ContactEventQueue>()
+            .map_or(Vec::new(), |queue| queue.drain())
+    }
+
     pub fn create_body(&mut self, def: &BodyDef) -> BodyHandle
         where U::BodyData: Default
     {
@@ -78,9 +122,23 @@ impl<U: UserDataTypes> World<U> {
     }
 
     pub fn create_body_with(&mut self, def: &BodyDef, data: U::BodyData) -> BodyHandle {
+        self.try_create_body_with(def, data).expect("world is locked")
+    }
+
+    pub fn try_create_body(&mut self, def: &BodyDef) -> Result<BodyHandle, WorldLocked>
+        where U::BodyData: Default
+    {
+        self.try_create_body_with(def, U::BodyData::default())
+    }
+
+    pub fn try_create_body_with(&mut self,
+                                def: &BodyDef,
+                                data: U::BodyData)
+                                -> Result<BodyHandle, WorldLocked> {
+        self.check_unlocked()?;
         unsafe {
             let body = ffi::World_create_body(self.mut_ptr(), def);
-            self.bodies.insert_with(|h| MetaBody::new(body, h, data))
+            Ok(self.bodies.insert_with(|h| MetaBody::new(body, h, data)))
         }
     }
 
@@ -92,31 +150,48 @@ impl<U: UserDataTypes> World<U> {
         self.bodies.get_mut(handle).expect("invalid body handle")
     }
 
-    pub fn try_body(&self, handle: BodyHandle) -> Option<Ref<MetaBody<U>>> {
-        self.bodies.get(handle)
+    pub fn try_body(&self, handle: BodyHandle) -> Result<Ref<MetaBody<U>>, Error> {
+        self.bodies.try_get(handle)
     }
 
-    pub fn try_body_mut(&self, handle: BodyHandle) -> Option<RefMut<MetaBody<U>>> {
-        self.bodies.get_mut(handle)
+    pub fn try_body_mut(&self, handle: BodyHandle) -> Result<RefMut<MetaBody<U>>, Error> {
+        self.bodies.try_get_mut(handle)
     }
 
     pub fn destroy_body(&mut self, handle: BodyHandle) {
-        let mut body = self.bodies.remove(handle);
+        self.try_destroy_body(handle).expect("body destruction failed")
+    }
+
+    pub fn try_destroy_body(&mut self, handle: BodyHandle) -> Result<(), Error> {
+        self.check_unlocked()?;
+        let mut body = self.bodies.try_remove(handle)?;
 
-        World::remove_body_joint_handles(&mut body, &mut self.joints);
+        let joints = World::remove_body_joint_handles(&mut body, &mut self.joints);
         unsafe {
             ffi::World_destroy_body(self.mut_ptr(), body.mut_ptr());
         }
+
+        if let Some(ref mut listener) = self.destruction_listener {
+            for (joint_handle, joint) in joints {
+                listener.say_goodbye_to_joint(joint_handle, joint.into_user_data());
+            }
+            for (fixture_handle, fixture) in body.into_fixtures() {
+                listener.say_goodbye_to_fixture(handle, fixture_handle, fixture.into_user_data());
+            }
+        }
+        Ok(())
     }
     
     pub fn bodies(&self) -> HandleIter<Body, MetaBody<U>> {
         self.bodies.iter()
     }
     
-    fn remove_body_joint_handles(body: &mut Body, joints: &mut HandleMap<MetaJoint<U>, dyn Joint>) {
-        for (_, joint) in body.joints() {
-            joints.remove(joint);
-        }
+    fn remove_body_joint_handles(body: &mut Body,
+                                 joints: &mut HandleMap<MetaJoint<U>, dyn Joint>)
+                                 -> Vec<(JointHandle, MetaJoint<U>)> {
+        body.joints()
+            .map(|(_, joint)| (joint, joints.remove(joint)))
+            .collect()
     }
 
     pub fn create_joint<JD: JointDef>(&mut self, def: &JD) -> JointHandle
@@ -126,22 +201,24 @@ impl<U: UserDataTypes> World<U> {
     }
 
     pub fn create_joint_with<JD: JointDef>(&mut self, def: &JD, data: U::JointData) -> JointHandle {
-        unsafe {
-            let joint = def.create(self);
-            self.joints.insert_with(|h| MetaJoint::new(joint, h, data))
-        }
+        self.try_create_joint_with(def, data).expect("joint creation failed")
     }
 
-    pub fn try_create_joint<JD: JointDef>(&mut self, def: &JD) -> Option<JointHandle>
+    pub fn try_create_joint<JD: JointDef>(&mut self, def: &JD) -> Result<JointHandle, Error>
         where U::JointData: Default
     {
         self.try_create_joint_with(def, U::JointData::default())
     }
 
-    pub fn try_create_joint_with<JD: JointDef>(&mut self, def: &JD, data: U::JointData) -> Option<JointHandle> {
+    pub fn try_create_joint_with<JD: JointDef>(&mut self,
+                                               def: &JD,
+                                               data: U::JointData)
+                                               -> Result<JointHandle, Error> {
+        self.check_unlocked()?;
+        def.validate(self)?;
         unsafe {
             let joint = def.try_create(self)?;
-            Some(self.joints.insert_with(|h| MetaJoint::new(joint, h, data)))
+            Ok(self.joints.insert_with(|h| MetaJoint::new(joint, h, data)))
         }
     }
 
@@ -153,32 +230,99 @@ impl<U: UserDataTypes> World<U> {
         self.joints.get_mut(handle).expect("invalid joint handle")
     }
 
-    pub fn try_joint(&self, handle: JointHandle) -> Option<Ref<MetaJoint<U>>> {
-        self.joints.get(handle)
+    pub fn try_joint(&self, handle: JointHandle) -> Result<Ref<MetaJoint<U>>, Error> {
+        self.joints.try_get(handle)
     }
 
-    pub fn try_joint_mut(&self, handle: JointHandle) -> Option<RefMut<MetaJoint<U>>> {
-        self.joints.get_mut(handle)
+    pub fn try_joint_mut(&self, handle: JointHandle) -> Result<RefMut<MetaJoint<U>>, Error> {
+        self.joints.try_get_mut(handle)
     }
 
     pub fn destroy_joint(&mut self, handle: JointHandle) {
-        let mut joint = self.joints.remove(handle);
+        self.try_destroy_joint(handle).expect("joint destruction failed")
+    }
+
+    pub fn try_destroy_joint(&mut self, handle: JointHandle) -> Result<(), Error> {
+        self.check_unlocked()?;
+        let mut joint = self.joints.try_remove(handle)?;
         unsafe {
             ffi::World_destroy_joint(self.mut_ptr(), joint.mut_base_ptr());
         }
+        Ok(())
     }
     
     pub fn joints(&self) -> HandleIter<dyn Joint, MetaJoint<U>> {
         self.joints.iter()
     }
+
+    /// The next body created gets `handle`, whose slot must be free.
+    #[doc(hidden)]
+    pub fn reserve_next_body(&mut self, handle: BodyHandle) -> Result<(), Error> {
+        self.bodies.reserve_next(handle)
+    }
+
+    /// The next joint created gets `handle`, whose slot must be free.
+    #[doc(hidden)]
+    pub fn reserve_next_joint(&mut self, handle: JointHandle) -> Result<(), Error> {
+        self.joints.reserve_next(handle)
+    }
+
+    #[doc(hidden)]
+    pub fn handle_layouts(&self) -> (HandleLayout, HandleLayout) {
+        (self.bodies.layout(), self.joints.layout())
+    }
+
+    #[doc(hidden)]
+    pub fn set_handle_layouts(&mut self,
+                              bodies: &HandleLayout,
+                              joints: &HandleLayout)
+                              -> Result<(), Error> {
+        self.bodies.set_layout(bodies)?;
+        self.joints.set_layout(joints)
+    }
         
     pub fn step(&mut self, time_step: f32, velocity_iterations: i32, position_iterations: i32) {
+        if self.recording_transforms {
+            for (_, body) in self.bodies.iter() {
+                body.borrow_mut().record_transform();
+            }
+        }
+
         unsafe {
             ffi::World_step(self.mut_ptr(),
                             time_step,
                             velocity_iterations,
                             position_iterations);
         }
+        self.apply_commands();
+    }
+
+    /// A handle to the command queue of this world, usable while the world
+    /// is locked. Contact listeners and filters are given it in their callbacks.
+    pub fn commands(&self) -> WorldCommands<U> {
+        self.commands.clone()
+    }
+
+    /// Applies the queued commands, this is done by `step` already.
+    /// Commands that keep queuing new ones are continued by the next call.
+    pub fn apply_commands(&mut self) {
+        let commands = self.commands.clone();
+        commands.apply(self);
+    }
+
+    /// When enabled, `step` records the transform of every body beforehand
+    /// to allow `MetaBody::interpolated_transform`.
+    pub fn set_recording_transforms(&mut self, flag: bool) {
+        self.recording_transforms = flag;
+        if !flag {
+            for (_, body) in self.bodies.iter() {
+                body.borrow_mut().forget_transform();
+            }
+        }
+    }
+
+    pub fn is_recording_transforms(&self) -> bool {
+        self.recording_transforms
     }
 
     pub fn clear_forces(&mut self) {
@@ -298,6 +442,10 @@ impl<U: UserDataTypes> World<U> {
         unsafe { ffi::World_is_locked(self.ptr()) }
     }
 
+    fn check_unlocked(&self) -> Result<(), WorldLocked> {
+        if self.is_locked() { Err(WorldLocked) } else { Ok(()) }
+    }
+
     pub fn set_auto_clearing_forces(&mut self, flag: bool) {
         unsafe { ffi::World_set_auto_clear_forces(self.mut_ptr(), flag) }
     }
@@ -307,7 +455,13 @@ impl<U: UserDataTypes> World<U> {
     }
 
     pub fn shift_origin(&mut self, origin: &Vec2) {
+        self.try_shift_origin(origin).expect("world is locked")
+    }
+
+    pub fn try_shift_origin(&mut self, origin: &Vec2) -> Result<(), WorldLocked> {
+        self.check_unlocked()?;
         unsafe { ffi::World_shift_origin(self.mut_ptr(), origin) }
+        Ok(())
     }
 
     pub fn profile<'a>(&'a self) -> &'a Profile {
@@ -323,6 +477,8 @@ impl<U: UserDataTypes> World<U> {
 
 impl<U: UserDataTypes> Drop for World<U> {
     fn drop(&mut self) {
+        // queued closures may hold clones of the queue
+        self.commands.clear();
         unsafe { ffi::World_drop(self.mut_ptr()) }
     }
 }
@@ -404,9 +560,9 @@ pub mod ffi {
                               p1: *const Vec2,
                               p2: *const Vec2);
         // pub fn World_get_body_list(slf: *mut World) -> *mut Body;
-        // pub fn World_get_body_list_const(slf: *const World) -> *const Body;
+        pub fn World_get_body_list_const(slf: *const World) -> *const Body;
         // pub fn World_get_joint_list(slf: *mut World) -> *mut Joint;
-        // pub fn World_get_joint_list_const(slf: *const World) -> *const Joint;
+        pub fn World_get_joint_list_const(slf: *const World) -> *const Joint;
         pub fn World_get_contact_list(slf: *mut World) -> *mut Contact;
         pub fn World_get_contact_list_const(slf: *const World) -> *const Contact;
         pub fn World_set_allow_sleeping(slf: *mut World, flag: bool);
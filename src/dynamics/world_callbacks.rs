@@ -1,4 +1,5 @@
 use std::mem;
+use std::ptr;
 use std::any::Any;
 use std::ops::*;
 use std::marker::PhantomData;
@@ -6,41 +7,64 @@ use wrap::*;
 use common::math::Vec2;
 use common::settings::MAX_MANIFOLD_POINTS;
 use collision::Manifold;
-use dynamics::world::BodyHandle;
+use dynamics::world::{BodyHandle, JointHandle};
+use dynamics::world::commands::WorldCommands;
 use dynamics::body::{Body, FixtureHandle};
 use dynamics::fixture::Fixture;
 use dynamics::contacts::Contact;
 use user_data::{InternalUserData, RawUserData, RawUserDataMut, UserData, UserDataTypes};
 
+/// Notified when joints and fixtures are implicitly destroyed
+/// because their body was destroyed.
+pub trait DestructionListener<U: UserDataTypes> {
+    fn say_goodbye_to_joint(&mut self, _: JointHandle, _: U::JointData) {}
+    fn say_goodbye_to_fixture(&mut self, _: BodyHandle, _: FixtureHandle, _: U::FixtureData) {}
+}
+
 pub trait ContactFilter<U: UserDataTypes>: Any {
-    fn should_collide(&mut self, body_a: BodyAccess<U>, fixture_a: FixtureAccess<U>,
-                                 body_b: BodyAccess<U>, fixture_b: FixtureAccess<U>) -> bool;
+    /// The world is locked, modifications have to be queued with `commands`.
+    fn should_collide(&mut self,
+                      body_a: BodyAccess<U>,
+                      fixture_a: FixtureAccess<U>,
+                      body_b: BodyAccess<U>,
+                      fixture_b: FixtureAccess<U>,
+                      commands: &WorldCommands<U>)
+                      -> bool;
 }
 
 #[doc(hidden)]
-pub struct ContactFilterLink {
+pub struct ContactFilterLink<U: UserDataTypes> {
     ptr: *mut ffi::ContactFilterLink,
     object: Option<Box<dyn Any>>,
+    // given to the callback, boxed so that it does not move with the world
+    binding: Box<FilterBinding<U>>,
 }
 
-wrap! { ffi::ContactFilterLink => custom ContactFilterLink }
+struct FilterBinding<U: UserDataTypes> {
+    filter: *mut (),
+    commands: WorldCommands<U>,
+}
 
-impl ContactFilterLink {
-    pub unsafe fn new() -> Self {
+impl<U: UserDataTypes> ContactFilterLink<U> {
+    pub unsafe fn new(commands: WorldCommands<U>) -> Self {
         ContactFilterLink {
             ptr: ffi::ContactFilterLink_alloc(),
             object: None,
+            binding: Box::new(FilterBinding {
+                filter: ptr::null_mut(),
+                commands: commands,
+            }),
         }
     }
-    
-    pub unsafe fn use_with<F, U>(&mut self, mut filter: Box<F>) -> *mut ffi::ContactFilter
-        where F: ContactFilter<U>, U: UserDataTypes
+
+    pub unsafe fn use_with<F>(&mut self, mut filter: Box<F>) -> *mut ffi::ContactFilter
+        where F: ContactFilter<U>
     {
-        ffi::ContactFilterLink_bind(self.mut_ptr(),
-                                    mem::transmute::<&mut F, _>(&mut *filter),
-                                    cfl_should_collide::<F, U>);
+        self.binding.filter = &mut *filter as *mut F as *mut ();
+        let binding = &mut *self.binding as *mut FilterBinding<U> as ffi::Any;
+        ffi::ContactFilterLink_bind(self.ptr, binding, cfl_should_collide::<F, U>);
         self.object = Some(filter);
-        ffi::ContactFilterLink_as_base(self.mut_ptr())
+        ffi::ContactFilterLink_as_base(self.ptr)
     }
 }
 
@@ -50,21 +74,24 @@ unsafe extern "C" fn cfl_should_collide<F, U>(object: ffi::Any,
                                               -> bool
         where F: ContactFilter<U>, U: UserDataTypes
 {
-    let filter = mem::transmute::<_, &mut F>(object);
+    let binding = &*(object as *const FilterBinding<U>);
+    let filter = &mut *(binding.filter as *mut F);
+    let commands = &binding.commands;
     body_access(ffi::Fixture_get_body(fixture_a), |ba|
     fixture_access(fixture_a, |fa|
     body_access(ffi::Fixture_get_body(fixture_b), |bb|
     fixture_access(fixture_b, |fb|
-        filter.should_collide(ba, fa, bb, fb)))))
+        filter.should_collide(ba, fa, bb, fb, commands)))))
 }
 
-impl Drop for ContactFilterLink {
+impl<U: UserDataTypes> Drop for ContactFilterLink<U> {
     fn drop(&mut self) {
-        unsafe { ffi::ContactFilterLink_drop(self.mut_ptr()) }
+        unsafe { ffi::ContactFilterLink_drop(self.ptr) }
     }
 }
 
 #[repr(C)]
+#[derive(Clone, Copy, Debug)]
 pub struct ContactImpulse {
     pub normal_impulses: [f32; MAX_MANIFOLD_POINTS],
     pub tangent_impulses: [f32; MAX_MANIFOLD_POINTS],
@@ -79,48 +106,67 @@ pub trait ContactListener<U: UserDataTypes>: Any {
 }
 
 #[doc(hidden)]
-pub struct ContactListenerLink {
+pub struct ContactListenerLink<U: UserDataTypes> {
     ptr: *mut ffi::ContactListenerLink,
     object: Option<Box<dyn Any>>,
+    // given to the callbacks, boxed so that it does not move with the world
+    binding: Box<ListenerBinding<U>>,
 }
 
-wrap! { ffi::ContactListenerLink => custom ContactListenerLink }
+struct ListenerBinding<U: UserDataTypes> {
+    listener: *mut (),
+    commands: WorldCommands<U>,
+}
 
-impl ContactListenerLink {
-    pub unsafe fn new() -> Self {
+impl<U: UserDataTypes> ContactListenerLink<U> {
+    pub unsafe fn new(commands: WorldCommands<U>) -> Self {
         ContactListenerLink {
             ptr: ffi::ContactListenerLink_alloc(),
             object: None,
+            binding: Box::new(ListenerBinding {
+                listener: ptr::null_mut(),
+                commands: commands,
+            }),
         }
     }
 
-    pub unsafe fn use_with<L, U>(&mut self, mut listener: Box<L>) -> *mut ffi::ContactListener
-        where L: ContactListener<U>, U: UserDataTypes
+    pub unsafe fn use_with<L>(&mut self, mut listener: Box<L>) -> *mut ffi::ContactListener
+        where L: ContactListener<U>
     {
-        ffi::ContactListenerLink_bind(self.mut_ptr(),
-                                      mem::transmute::<&mut L, _>(&mut *listener),             cll_begin_contact::<L, U>,
+        self.binding.listener = &mut *listener as *mut L as *mut ();
+        let binding = &mut *self.binding as *mut ListenerBinding<U> as ffi::Any;
+        ffi::ContactListenerLink_bind(self.ptr,
+                                      binding,
+                                      cll_begin_contact::<L, U>,
                                       cll_end_contact::<L, U>,
                                       cll_pre_solve::<L, U>,
                                       cll_post_solve::<L, U>);
         self.object = Some(listener);
-        ffi::ContactListenerLink_as_base(self.mut_ptr())
+        ffi::ContactListenerLink_as_base(self.ptr)
     }
 }
 
+unsafe fn bound_listener<'a, L, U>(object: ffi::Any) -> (&'a mut L, &'a WorldCommands<U>)
+    where L: ContactListener<U>, U: UserDataTypes
+{
+    let binding = &*(object as *const ListenerBinding<U>);
+    (&mut *(binding.listener as *mut L), &binding.commands)
+}
+
 unsafe extern "C" fn cll_begin_contact<L, U>(object: ffi::Any,
                                              contact: *mut ffi::Contact)
     where L: ContactListener<U>, U: UserDataTypes
 {
-    let listener = mem::transmute::<_, &mut L>(object);
-    contact_access(contact, |c| listener.begin_contact(c))
+    let (listener, commands) = bound_listener::<L, U>(object);
+    contact_access(contact, commands, |c| listener.begin_contact(c))
 }
 
 unsafe extern "C" fn cll_end_contact<L, U>(object: ffi::Any,
                                            contact: *mut ffi::Contact)
     where L: ContactListener<U>, U: UserDataTypes
 {
-    let listener = mem::transmute::<_, &mut L>(object);
-    contact_access(contact, |c| listener.end_contact(c))
+    let (listener, commands) = bound_listener::<L, U>(object);
+    contact_access(contact, commands, |c| listener.end_contact(c))
 }
 
 unsafe extern "C" fn cll_pre_solve<L, U>(object: ffi::Any,
@@ -129,8 +175,8 @@ unsafe extern "C" fn cll_pre_solve<L, U>(object: ffi::Any,
     where L: ContactListener<U>, U: UserDataTypes
 {
     assert!(!old_manifold.is_null());
-    let listener = mem::transmute::<_, &mut L>(object);
-    contact_access(contact, |c| listener.pre_solve(c, &*old_manifold))
+    let (listener, commands) = bound_listener::<L, U>(object);
+    contact_access(contact, commands, |c| listener.pre_solve(c, &*old_manifold))
 }
 
 unsafe extern "C" fn cll_post_solve<L, U>(object: ffi::Any,
@@ -139,13 +185,111 @@ unsafe extern "C" fn cll_post_solve<L, U>(object: ffi::Any,
     where L: ContactListener<U>, U: UserDataTypes
 {
     assert!(!impulse.is_null());
-    let listener = mem::transmute::<_, &mut L>(object);
-    contact_access(contact, |c| listener.post_solve(c, &*impulse))
+    let (listener, commands) = bound_listener::<L, U>(object);
+    contact_access(contact, commands, |c| listener.post_solve(c, &*impulse))
 }
 
-impl Drop for ContactListenerLink {
+impl<U: UserDataTypes> ContactListenerLink<U> {
+    pub fn listener_mut<L: Any>(&mut self) -> Option<&mut L> {
+        self.object.as_mut().and_then(|object| object.downcast_mut())
+    }
+}
+
+impl<U: UserDataTypes> Drop for ContactListenerLink<U> {
     fn drop(&mut self) {
-        unsafe { ffi::ContactListenerLink_drop(self.mut_ptr()) }
+        unsafe { ffi::ContactListenerLink_drop(self.ptr) }
+    }
+}
+
+/// World space contact points, as given by `Contact::world_manifold`.
+#[derive(Clone, Debug)]
+pub struct ContactPoints {
+    pub normal: Vec2,
+    pub points: Vec<Vec2>,
+}
+
+impl ContactPoints {
+    fn new(contact: &Contact) -> ContactPoints {
+        let world_manifold = contact.world_manifold();
+        let count = contact.manifold().count as usize;
+        ContactPoints {
+            normal: world_manifold.normal,
+            points: world_manifold.points[..count].to_vec(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum ContactEventKind {
+    Begin(ContactPoints),
+    End,
+    PreSolve(ContactPoints),
+    PostSolve(ContactImpulse),
+}
+
+#[derive(Clone, Debug)]
+pub struct ContactEvent {
+    pub fixture_a: (BodyHandle, FixtureHandle),
+    pub fixture_b: (BodyHandle, FixtureHandle),
+    pub kind: ContactEventKind,
+}
+
+/// A contact listener recording owned events, to be handled with
+/// `World::drain_contact_events` once the step is over.
+pub struct ContactEventQueue {
+    pub record_pre_solve: bool,
+    pub record_post_solve: bool,
+    events: Vec<ContactEvent>,
+}
+
+impl ContactEventQueue {
+    pub fn new() -> ContactEventQueue {
+        ContactEventQueue {
+            record_pre_solve: false,
+            record_post_solve: true,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn drain(&mut self) -> Vec<ContactEvent> {
+        mem::replace(&mut self.events, Vec::new())
+    }
+
+    fn push(&mut self, contact: &Contact, kind: ContactEventKind) {
+        self.record(contact.fixture_a(), contact.fixture_b(), kind);
+    }
+
+    fn record(&mut self,
+              fixture_a: (BodyHandle, FixtureHandle),
+              fixture_b: (BodyHandle, FixtureHandle),
+              kind: ContactEventKind) {
+        self.events.push(ContactEvent {
+            fixture_a: fixture_a,
+            fixture_b: fixture_b,
+            kind: kind,
+        });
+    }
+}
+
+impl<U: UserDataTypes> ContactListener<U> for ContactEventQueue {
+    fn begin_contact(&mut self, ca: ContactAccess<U>) {
+        self.push(ca.contact, ContactEventKind::Begin(ContactPoints::new(ca.contact)));
+    }
+
+    fn end_contact(&mut self, ca: ContactAccess<U>) {
+        self.push(ca.contact, ContactEventKind::End);
+    }
+
+    fn pre_solve(&mut self, ca: ContactAccess<U>, _: &Manifold) {
+        if self.record_pre_solve && ca.contact.is_touching() {
+            self.push(ca.contact, ContactEventKind::PreSolve(ContactPoints::new(ca.contact)));
+        }
+    }
+
+    fn post_solve(&mut self, ca: ContactAccess<U>, impulse: &ContactImpulse) {
+        if self.record_post_solve {
+            self.push(ca.contact, ContactEventKind::PostSolve(*impulse));
+        }
     }
 }
 
@@ -154,11 +298,16 @@ pub struct ContactAccess<'a, U: UserDataTypes> {
     pub body_a: BodyAccess<'a, U>,
     pub fixture_a: FixtureAccess<'a, U>,
     pub body_b: BodyAccess<'a, U>,
-    pub fixture_b: FixtureAccess<'a, U>
+    pub fixture_b: FixtureAccess<'a, U>,
+    /// The world is locked, modifications have to be queued.
+    pub commands: &'a WorldCommands<U>,
 }
 
 #[inline(always)]
-unsafe fn contact_access<F, O, U>(contact: *mut ffi::Contact, f: F) -> O
+unsafe fn contact_access<F, O, U>(contact: *mut ffi::Contact,
+                                  commands: &WorldCommands<U>,
+                                  f: F)
+                                  -> O
     where F: for<'a> FnOnce(ContactAccess<'a, U>) -> O,
           U: UserDataTypes
 {
@@ -175,7 +324,8 @@ unsafe fn contact_access<F, O, U>(contact: *mut ffi::Contact, f: F) -> O
             body_a: ba,
             fixture_a: fa,
             body_b: bb,
-            fixture_b: fb
+            fixture_b: fb,
+            commands: commands,
         })))))
 }
 
@@ -277,6 +427,18 @@ impl<F> QueryCallback for F
     }
 }
 
+pub trait ShapeQueryCallback {
+    fn report_fixture(&mut self, body: BodyHandle, fixture: FixtureHandle, child_index: i32) -> bool;
+}
+
+impl<F> ShapeQueryCallback for F
+    where F: FnMut(BodyHandle, FixtureHandle, i32) -> bool
+{
+    fn report_fixture(&mut self, body: BodyHandle, fixture: FixtureHandle, child_index: i32) -> bool {
+        self(body, fixture, child_index)
+    }
+}
+
 wrap! { ffi::QueryCallbackLink => #[doc(hidden)] pub QueryCallbackLink }
 
 impl QueryCallbackLink {
@@ -363,6 +525,52 @@ impl Drop for RayCastCallbackLink {
     }
 }
 
+#[cfg(test)]
+mod test {
+    use super::*;
+    use handle::TypedHandle;
+
+    fn fixture(index: usize) -> (BodyHandle, FixtureHandle) {
+        (TypedHandle::new(index, 0), TypedHandle::new(index, 0))
+    }
+
+    fn summary(events: &[ContactEvent]) -> Vec<(usize, &'static str)> {
+        events.iter()
+            .map(|event| {
+                let kind = match event.kind {
+                    ContactEventKind::Begin(_) => "begin",
+                    ContactEventKind::End => "end",
+                    ContactEventKind::PreSolve(_) => "pre",
+                    ContactEventKind::PostSolve(_) => "post",
+                };
+                (event.fixture_b.1.index(), kind)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn drain_keeps_order() {
+        let points = || ContactPoints { normal: Vec2 { x: 0., y: 1. }, points: Vec::new() };
+        let impulse = ContactImpulse {
+            normal_impulses: [0.; MAX_MANIFOLD_POINTS],
+            tangent_impulses: [0.; MAX_MANIFOLD_POINTS],
+            count: 0,
+        };
+        let mut queue = ContactEventQueue::new();
+        queue.record(fixture(0), fixture(1), ContactEventKind::Begin(points()));
+        queue.record(fixture(0), fixture(2), ContactEventKind::Begin(points()));
+        queue.record(fixture(0), fixture(1), ContactEventKind::PostSolve(impulse));
+        queue.record(fixture(0), fixture(1), ContactEventKind::End);
+
+        assert_eq!(summary(&queue.drain()),
+                   vec![(1, "begin"), (2, "begin"), (1, "post"), (1, "end")]);
+        assert!(queue.drain().is_empty());
+
+        queue.record(fixture(0), fixture(2), ContactEventKind::End);
+        assert_eq!(summary(&queue.drain()), vec![(2, "end")]);
+    }
+}
+
 #[doc(hidden)]
 pub mod ffi {
     pub use ffi::Any;
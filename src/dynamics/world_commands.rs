@@ -0,0 +1,247 @@
+use std::mem;
+use std::rc::Rc;
+use std::cell::RefCell;
+use common::math::Vec2;
+use collision::shapes::UnknownShape;
+use user_data::UserDataTypes;
+use error::Error;
+use dynamics::world::{World, BodyHandle, JointHandle};
+use dynamics::body::{BodyDef, BodyType, FixtureHandle};
+use dynamics::fixture::FixtureDef;
+use dynamics::joints::JointDef;
+
+type Then<U, H> = Option<Box<dyn FnOnce(&mut World<U>, H)>>;
+
+enum Command<U: UserDataTypes> {
+    CreateBody(BodyDef, U::BodyData, Then<U, BodyHandle>),
+    DestroyBody(BodyHandle),
+    CreateFixture(BodyHandle,
+                  UnknownShape,
+                  FixtureDef,
+                  U::FixtureData,
+                  Then<U, (BodyHandle, FixtureHandle)>),
+    DestroyFixture(BodyHandle, FixtureHandle),
+    DestroyJoint(JointHandle),
+    ApplyLinearImpulse(BodyHandle, Vec2, Vec2, bool),
+    ApplyAngularImpulse(BodyHandle, f32, bool),
+    SetBodyType(BodyHandle, BodyType),
+    Custom(Box<dyn FnOnce(&mut World<U>) -> Result<(), Error>>),
+}
+
+/// Commands queued while applying are applied in the same call,
+/// up to this many passes. The rest is left for the next call.
+const MAX_PASSES: usize = 16;
+
+/// Queues world modifications while the world is locked,
+/// they are applied at the end of `World::step`.
+///
+/// Contact listeners get it with `ContactAccess::commands` and contact
+/// filters as an argument of `should_collide`, otherwise it is obtained with
+/// `World::commands`. Commands that fail, e.g. because of a stale handle, are
+/// skipped and their errors are kept until `drain_errors` is called.
+///
+/// A queued closure capturing a clone keeps the queue alive until it is
+/// applied, the world discards the commands still queued when dropped so
+/// that such cycles are broken.
+pub struct WorldCommands<U: UserDataTypes> {
+    queue: Rc<RefCell<Vec<Command<U>>>>,
+    errors: Rc<RefCell<Vec<Error>>>,
+}
+
+impl<U: UserDataTypes> Clone for WorldCommands<U> {
+    fn clone(&self) -> Self {
+        WorldCommands {
+            queue: self.queue.clone(),
+            errors: self.errors.clone(),
+        }
+    }
+}
+
+impl<U: UserDataTypes> WorldCommands<U> {
+    #[doc(hidden)]
+    pub fn new() -> Self {
+        WorldCommands {
+            queue: Rc::new(RefCell::new(Vec::new())),
+            errors: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+
+    fn push(&self, command: Command<U>) {
+        self.queue.borrow_mut().push(command);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.borrow().is_empty()
+    }
+
+    /// Discards the queued commands without applying them.
+    pub fn clear(&self) {
+        // dropped outside of the borrow, in case they hold clones
+        let commands = mem::replace(&mut *self.queue.borrow_mut(), Vec::new());
+        drop(commands);
+    }
+
+    /// Returns the errors of the commands that failed so far.
+    pub fn drain_errors(&self) -> Vec<Error> {
+        mem::replace(&mut *self.errors.borrow_mut(), Vec::new())
+    }
+
+    pub fn create_body(&self, def: BodyDef)
+        where U::BodyData: Default
+    {
+        self.create_body_with(def, U::BodyData::default())
+    }
+
+    pub fn create_body_with(&self, def: BodyDef, data: U::BodyData) {
+        self.push(Command::CreateBody(def, data, None));
+    }
+
+    /// Like `create_body_with`, `then` is called with the new body once it is
+    /// created, to add fixtures for example.
+    pub fn create_body_then<F>(&self, def: BodyDef, data: U::BodyData, then: F)
+        where F: FnOnce(&mut World<U>, BodyHandle) + 'static
+    {
+        self.push(Command::CreateBody(def, data, Some(Box::new(then))));
+    }
+
+    pub fn destroy_body(&self, body: BodyHandle) {
+        self.push(Command::DestroyBody(body));
+    }
+
+    pub fn create_fixture(&self, body: BodyHandle, shape: UnknownShape, def: FixtureDef)
+        where U::FixtureData: Default
+    {
+        self.create_fixture_with(body, shape, def, U::FixtureData::default())
+    }
+
+    pub fn create_fixture_with(&self,
+                               body: BodyHandle,
+                               shape: UnknownShape,
+                               def: FixtureDef,
+                               data: U::FixtureData) {
+        self.push(Command::CreateFixture(body, shape, def, data, None));
+    }
+
+    /// Like `create_fixture_with`, `then` is called with the new fixture once
+    /// it is created.
+    pub fn create_fixture_then<F>(&self,
+                                  body: BodyHandle,
+                                  shape: UnknownShape,
+                                  def: FixtureDef,
+                                  data: U::FixtureData,
+                                  then: F)
+        where F: FnOnce(&mut World<U>, (BodyHandle, FixtureHandle)) + 'static
+    {
+        self.push(Command::CreateFixture(body, shape, def, data, Some(Box::new(then))));
+    }
+
+    pub fn destroy_fixture(&self, body: BodyHandle, fixture: FixtureHandle) {
+        self.push(Command::DestroyFixture(body, fixture));
+    }
+
+    pub fn create_joint<JD>(&self, def: JD)
+        where JD: JointDef + 'static,
+              U::JointData: Default + 'static
+    {
+        self.create_joint_with(def, U::JointData::default())
+    }
+
+    pub fn create_joint_with<JD>(&self, def: JD, data: U::JointData)
+        where JD: JointDef + 'static,
+              U::JointData: 'static
+    {
+        self.push(Command::Custom(Box::new(move |world: &mut World<U>| {
+            world.try_create_joint_with(&def, data).map(|_| ())
+        })));
+    }
+
+    /// Like `create_joint_with`, `then` is called with the new joint once it
+    /// is created.
+    pub fn create_joint_then<JD, F>(&self, def: JD, data: U::JointData, then: F)
+        where JD: JointDef + 'static,
+              U::JointData: 'static,
+              F: FnOnce(&mut World<U>, JointHandle) + 'static
+    {
+        self.push(Command::Custom(Box::new(move |world: &mut World<U>| {
+            let joint = world.try_create_joint_with(&def, data)?;
+            then(world, joint);
+            Ok(())
+        })));
+    }
+
+    pub fn destroy_joint(&self, joint: JointHandle) {
+        self.push(Command::DestroyJoint(joint));
+    }
+
+    pub fn apply_linear_impulse(&self, body: BodyHandle, impulse: Vec2, point: Vec2, wake: bool) {
+        self.push(Command::ApplyLinearImpulse(body, impulse, point, wake));
+    }
+
+    pub fn apply_angular_impulse(&self, body: BodyHandle, impulse: f32, wake: bool) {
+        self.push(Command::ApplyAngularImpulse(body, impulse, wake));
+    }
+
+    pub fn set_body_type(&self, body: BodyHandle, body_type: BodyType) {
+        self.push(Command::SetBodyType(body, body_type));
+    }
+
+    /// Queues an arbitrary modification.
+    pub fn push_fn<F: FnOnce(&mut World<U>) + 'static>(&self, f: F) {
+        self.push(Command::Custom(Box::new(move |world: &mut World<U>| {
+            f(world);
+            Ok(())
+        })));
+    }
+
+    #[doc(hidden)]
+    pub fn apply(&self, world: &mut World<U>) {
+        // applying commands may queue new ones, a command queuing itself
+        // would otherwise never let this return
+        for _ in 0..MAX_PASSES {
+            let commands = mem::replace(&mut *self.queue.borrow_mut(), Vec::new());
+            if commands.is_empty() {
+                break;
+            }
+
+            for command in commands {
+                if let Err(e) = apply(command, world) {
+                    self.errors.borrow_mut().push(e);
+                }
+            }
+        }
+    }
+}
+
+fn apply<U: UserDataTypes>(command: Command<U>, world: &mut World<U>) -> Result<(), Error> {
+    match command {
+        Command::CreateBody(def, data, then) => {
+            let body = world.try_create_body_with(&def, data)?;
+            if let Some(then) = then {
+                then(world, body);
+            }
+        }
+        Command::DestroyBody(body) => world.try_destroy_body(body)?,
+        Command::CreateFixture(body, shape, mut def, data, then) => {
+            let fixture = world.try_body_mut(body)?
+                .try_create_fixture_with(&shape, &mut def, data)?;
+            if let Some(then) = then {
+                then(world, (body, fixture));
+            }
+        }
+        Command::DestroyFixture(body, fixture) => {
+            world.try_body_mut(body)?.try_destroy_fixture(fixture)?
+        }
+        Command::DestroyJoint(joint) => world.try_destroy_joint(joint)?,
+        Command::ApplyLinearImpulse(body, impulse, point, wake) => {
+            world.try_body_mut(body)?.apply_linear_impulse(&impulse, &point, wake)
+        }
+        Command::ApplyAngularImpulse(body, impulse, wake) => {
+            world.try_body_mut(body)?.apply_angular_impulse(impulse, wake)
+        }
+        Command::SetBodyType(body, body_type) => {
+            world.try_body_mut(body)?.try_set_body_type(body_type)?
+        }
+        Command::Custom(f) => f(world)?,
+    }
+    Ok(())
+}
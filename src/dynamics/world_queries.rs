@@ -0,0 +1,438 @@
+use std::vec;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use wrap::*;
+use common::math::{Vec2, Transform, Sweep};
+use collision::{distance, time_of_impact, test_overlap, AABB};
+use collision::distance::{Proxy, SimplexCache};
+use collision::time_of_impact::State;
+use collision::shapes::Shape;
+use user_data::UserDataTypes;
+use dynamics::world::{World, BodyHandle};
+use dynamics::body::{FixtureHandle, MetaBody};
+use dynamics::fixture::{Filter, Fixture, MetaFixture};
+use user_data::RawUserData;
+use dynamics::world::callbacks::{QueryCallback, RayCastCallback, ShapeQueryCallback};
+
+struct ShapeCastHit {
+    point: Vec2,
+    normal: Vec2,
+    fraction: f32,
+}
+
+/// Restricts world queries to some fixtures.
+///
+/// Like `World::body`, the queries testing shapes panic when the caller holds
+/// one of the bodies they test mutably borrowed.
+#[derive(Clone, Debug)]
+pub struct QueryFilter {
+    /// Tested against the fixtures' filter data like in a contact filter.
+    pub filter: Filter,
+    pub ignore_sensors: bool,
+    pub ignored_bodies: Vec<BodyHandle>,
+}
+
+impl QueryFilter {
+    pub fn new() -> QueryFilter {
+        QueryFilter {
+            filter: Filter::new(),
+            ignore_sensors: false,
+            ignored_bodies: Vec::new(),
+        }
+    }
+
+    pub fn accepts(&self, fixture: &Fixture) -> bool {
+        !(self.ignore_sensors && fixture.is_sensor()) &&
+        !self.ignored_bodies.contains(&fixture.body()) &&
+        self.filter.should_collide(fixture.filter_data())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RayCastHit {
+    pub body: BodyHandle,
+    pub fixture: FixtureHandle,
+    pub child_index: i32,
+    pub point: Vec2,
+    pub normal: Vec2,
+    pub fraction: f32,
+}
+
+impl<U: UserDataTypes> World<U> {
+    /// Sweeps `shape` from `from` along `translation` and reports the fixtures
+    /// it hits, following the same protocol as `ray_cast`: the callback
+    /// returns -1 to ignore a fixture, 0 to stop, or the fraction to clip
+    /// the sweep to. The reported normal points away from the hit fixture.
+    pub fn shape_cast<S, C>(&self,
+                            shape: &S,
+                            from: &Transform,
+                            translation: Vec2,
+                            filter: Option<&QueryFilter>,
+                            callback: &mut C)
+        where S: Shape,
+              C: RayCastCallback
+    {
+        let to = Transform {
+            pos: from.pos + translation,
+            rot: from.rot,
+        };
+
+        let candidates = candidates(self, (0..shape.child_count()).map(|child| {
+            shape.compute_aabb(from, child).combine(&shape.compute_aabb(&to, child))
+        }));
+
+        let mut max_fraction = 1.;
+        for (b, f) in candidates {
+            let hit = self.with_fixture(b, f, |body, fixture| {
+                if filter.map_or(false, |filter| !filter.accepts(fixture)) {
+                    return None;
+                }
+                let target = fixture.shape();
+                cast_against(shape, from, &to, &*target, body.transform(), max_fraction)
+            });
+
+            if let Some(hit) = hit {
+                let value = callback.report_fixture(b, f, &hit.point, &hit.normal, hit.fraction);
+                if value == 0. {
+                    return;
+                } else if value > 0. {
+                    max_fraction = value;
+                }
+            }
+        }
+    }
+
+    /// Reports every fixture child that really overlaps `shape` placed at `xf`,
+    /// not just the ones whose AABB does. When `filter` is given, the fixtures
+    /// it rejects are skipped.
+    pub fn query_shape<S, C>(&self,
+                             shape: &S,
+                             xf: &Transform,
+                             filter: Option<&QueryFilter>,
+                             callback: &mut C)
+        where S: Shape,
+              C: ShapeQueryCallback
+    {
+        let candidates = candidates(self, (0..shape.child_count()).map(|child| {
+            shape.compute_aabb(xf, child)
+        }));
+
+        for (b, f) in candidates {
+            let overlapping: Vec<i32> = self.with_fixture(b, f, |body, fixture| {
+                if filter.map_or(false, |filter| !filter.accepts(fixture)) {
+                    return Vec::new();
+                }
+
+                let target = fixture.shape();
+                (0..target.child_count())
+                    .filter(|&j| {
+                        (0..shape.child_count()).any(|i| {
+                            test_overlap(shape, i, xf, &*target, j, body.transform())
+                        })
+                    })
+                    .collect()
+            });
+
+            for child in overlapping {
+                if !callback.report_fixture(b, f, child) {
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Reports every fixture whose shape contains `p`. When `filter` is given,
+    /// the fixtures it rejects are skipped.
+    pub fn query_point<C: QueryCallback>(&self,
+                                         p: &Vec2,
+                                         filter: Option<&QueryFilter>,
+                                         callback: &mut C) {
+        let aabb = AABB {
+            lower: *p,
+            upper: *p,
+        };
+
+        for (b, f) in candidates(self, Some(aabb).into_iter()) {
+            let contains = self.with_fixture(b, f, |_, fixture| {
+                filter.map_or(true, |filter| filter.accepts(fixture)) && fixture.test_point(p)
+            });
+
+            if contains && !callback.report_fixture(b, f) {
+                return;
+            }
+        }
+    }
+
+    pub fn query_point_iter(&self,
+                            p: &Vec2,
+                            filter: Option<&QueryFilter>)
+                            -> vec::IntoIter<(BodyHandle, FixtureHandle)> {
+        let mut result = Vec::new();
+        self.query_point(p, filter, &mut |b, f| {
+            result.push((b, f));
+            true
+        });
+        result.into_iter()
+    }
+
+    pub fn query_aabb_filtered<C: QueryCallback>(&self,
+                                                 callback: &mut C,
+                                                 aabb: &AABB,
+                                                 filter: &QueryFilter) {
+        self.query_aabb(&mut |b, f| !self.accepts(filter, b, f) || callback.report_fixture(b, f),
+                        aabb);
+    }
+
+    /// Like `ray_cast` but fixtures rejected by `filter` are never reported.
+    pub fn ray_cast_filtered<C: RayCastCallback>(&self,
+                                                 callback: &mut C,
+                                                 p1: &Vec2,
+                                                 p2: &Vec2,
+                                                 filter: &QueryFilter) {
+        self.ray_cast(&mut |b, f, p: &Vec2, n: &Vec2, fraction| {
+                          if self.accepts(filter, b, f) {
+                              callback.report_fixture(b, f, p, n, fraction)
+                          } else {
+                              -1.
+                          }
+                      },
+                      p1,
+                      p2);
+    }
+
+    pub fn ray_cast_closest(&self,
+                            p1: &Vec2,
+                            p2: &Vec2,
+                            filter: Option<&QueryFilter>)
+                            -> Option<RayCastHit> {
+        let mut closest = None;
+        self.ray_cast_hits(p1, p2, filter, |hit| {
+            // the ray is clipped to each reported fraction,
+            // so the last report is the closest one
+            let fraction = hit.fraction;
+            closest = Some(hit);
+            fraction
+        });
+        closest
+    }
+
+    /// Every fixture child crossed by the segment, sorted by fraction.
+    pub fn ray_cast_all(&self,
+                        p1: &Vec2,
+                        p2: &Vec2,
+                        filter: Option<&QueryFilter>)
+                        -> Vec<RayCastHit> {
+        let mut hits = Vec::new();
+        self.ray_cast_hits(p1, p2, filter, |hit| {
+            hits.push(hit);
+            1.
+        });
+        hits.sort_by(|a, b| a.fraction.partial_cmp(&b.fraction).unwrap_or(Ordering::Equal));
+        hits
+    }
+
+    pub fn query_aabb_collect(&self,
+                              aabb: &AABB,
+                              filter: Option<&QueryFilter>)
+                              -> Vec<(BodyHandle, FixtureHandle)> {
+        let mut result = Vec::new();
+        {
+            let mut callback = |b, f| {
+                result.push((b, f));
+                true
+            };
+            match filter {
+                Some(filter) => self.query_aabb_filtered(&mut callback, aabb, filter),
+                None => self.query_aabb(&mut callback, aabb),
+            }
+        }
+        result
+    }
+
+    /// Like `ray_cast_filtered`, the hits also tell the child of the fixture.
+    fn ray_cast_hits<F>(&self, p1: &Vec2, p2: &Vec2, filter: Option<&QueryFilter>, mut f: F)
+        where F: FnMut(RayCastHit) -> f32
+    {
+        let mut callback = |b, fixture, child_index, p: &Vec2, n: &Vec2, fraction| {
+            if filter.map_or(false, |filter| !self.accepts(filter, b, fixture)) {
+                return -1.;
+            }
+            f(RayCastHit {
+                body: b,
+                fixture: fixture,
+                child_index: child_index,
+                point: *p,
+                normal: *n,
+                fraction: fraction,
+            })
+        };
+        let report = report_child(&callback);
+        unsafe {
+            ffi::World_ray_cast_children(self.ptr(),
+                                         &mut callback as *mut _ as ffi::Any,
+                                         report,
+                                         p1,
+                                         p2);
+        }
+    }
+
+    fn accepts(&self, filter: &QueryFilter, b: BodyHandle, f: FixtureHandle) -> bool {
+        self.with_fixture(b, f, |_, fixture| filter.accepts(fixture))
+    }
+
+    fn with_fixture<F, R>(&self, b: BodyHandle, f: FixtureHandle, g: F) -> R
+        where F: FnOnce(&MetaBody<U>, &MetaFixture<U>) -> R
+    {
+        let body = self.body(b);
+        let fixture = body.fixture(f);
+        g(&body, &fixture)
+    }
+}
+
+fn candidates<U, I>(world: &World<U>, aabbs: I) -> Vec<(BodyHandle, FixtureHandle)>
+    where U: UserDataTypes,
+          I: Iterator<Item = AABB>
+{
+    let mut candidates = Vec::new();
+    let mut seen = HashSet::new();
+    for aabb in aabbs {
+        world.query_aabb(&mut |b: BodyHandle, f: FixtureHandle| {
+                             if seen.insert((b.index(), f.index())) {
+                                 candidates.push((b, f));
+                             }
+                             true
+                         },
+                         &aabb);
+    }
+    candidates
+}
+
+fn cast_against<A, B>(shape: &A,
+                      from: &Transform,
+                      to: &Transform,
+                      target: &B,
+                      xf: &Transform,
+                      max_fraction: f32)
+                      -> Option<ShapeCastHit>
+    where A: Shape,
+          B: Shape
+{
+    let zero = Vec2 { x: 0., y: 0. };
+    let sweep_a = Sweep {
+        local_center: zero,
+        c0: from.pos,
+        c: to.pos,
+        a0: from.rot.angle(),
+        a: from.rot.angle(),
+        alpha0: 0.,
+    };
+    let sweep_b = Sweep {
+        local_center: zero,
+        c0: xf.pos,
+        c: xf.pos,
+        a0: xf.rot.angle(),
+        a: xf.rot.angle(),
+        alpha0: 0.,
+    };
+
+    let mut best: Option<ShapeCastHit> = None;
+    for i in 0..shape.child_count() {
+        for j in 0..target.child_count() {
+            let t_max = best.as_ref().map_or(max_fraction, |hit| hit.fraction);
+            let output = time_of_impact::Input::new(Proxy::new(shape, i),
+                                                    Proxy::new(target, j),
+                                                    sweep_a.clone(),
+                                                    sweep_b.clone(),
+                                                    t_max)
+                .query();
+
+            let t = match output.state {
+                State::Touching if output.t < t_max => output.t,
+                State::Overlapped => 0.,
+                _ => continue,
+            };
+
+            let at = Transform {
+                pos: from.pos + (to.pos - from.pos) * t,
+                rot: from.rot,
+            };
+            let mut cache = SimplexCache {
+                metric: 0.,
+                count: 0,
+                index_a: [0; 3],
+                index_b: [0; 3],
+            };
+            let output = distance::Input::new(Proxy::new(shape, i),
+                                              Proxy::new(target, j),
+                                              at,
+                                              xf.clone(),
+                                              false)
+                .query(&mut cache);
+
+            let mut normal = output.point_a - output.point_b;
+            if normal.norm() > ::std::f32::EPSILON {
+                normal = normal / normal.norm();
+            } else {
+                let dir = from.pos - to.pos;
+                normal = if dir.norm() > 0. { dir / dir.norm() } else { zero };
+            }
+
+            best = Some(ShapeCastHit {
+                point: output.point_b + normal * target.radius(),
+                normal: normal,
+                fraction: t,
+            });
+        }
+    }
+    best
+}
+
+type ReportChild = unsafe extern "C" fn(ffi::Any,
+                                        *mut ffi::Fixture,
+                                        i32,
+                                        *const Vec2,
+                                        *const Vec2,
+                                        f32)
+                                        -> f32;
+
+/// The trampoline calling `C`, taking the callback only to infer its type.
+fn report_child<C>(_: &C) -> ReportChild
+    where C: FnMut(BodyHandle, FixtureHandle, i32, &Vec2, &Vec2, f32) -> f32
+{
+    unsafe extern "C" fn report<C>(object: ffi::Any,
+                                   fixture: *mut ffi::Fixture,
+                                   child_index: i32,
+                                   point: *const Vec2,
+                                   normal: *const Vec2,
+                                   fraction: f32)
+                                   -> f32
+        where C: FnMut(BodyHandle, FixtureHandle, i32, &Vec2, &Vec2, f32) -> f32
+    {
+        let callback = &mut *(object as *mut C);
+        let body_handle = WrappedRef::new(Fixture::from_ffi(fixture)).body();
+        callback(body_handle, fixture.handle(), child_index, &*point, &*normal, fraction)
+    }
+    report::<C>
+}
+
+#[doc(hidden)]
+pub mod ffi {
+    pub use ffi::Any;
+    pub use dynamics::world::ffi::World;
+    pub use dynamics::fixture::ffi::Fixture;
+    use common::math::Vec2;
+
+    extern "C" {
+        pub fn World_ray_cast_children(slf: *const World,
+                                       object: Any,
+                                       report_child: unsafe extern "C" fn(Any,
+                                                                          *mut Fixture,
+                                                                          i32,
+                                                                          *const Vec2,
+                                                                          *const Vec2,
+                                                                          f32)
+                                                                          -> f32,
+                                       p1: *const Vec2,
+                                       p2: *const Vec2);
+    }
+}
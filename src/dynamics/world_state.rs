@@ -0,0 +1,605 @@
+//! The state Box2D carries from one step to the next, which the public API
+//! does not expose: sweeps and sleep timers of the bodies, warm starting
+//! impulses of the joints and contacts, and the broad-phase tree.
+
+use std::collections::{HashMap, HashSet};
+use std::mem;
+use std::ptr;
+use wrap::*;
+use error::Error;
+use common::math::Vec2;
+use common::settings::MAX_MANIFOLD_POINTS;
+use collision::AABB;
+use collision::shapes::ShapeType;
+use user_data::UserDataTypes;
+use dynamics::world::{World, BodyHandle, JointHandle};
+use dynamics::body::{Body, BodyType, FixtureHandle};
+use dynamics::joints::UnknownJoint;
+
+/// What `World::restore_state` overwrites, see `World::save_state`.
+#[derive(Clone, Debug)]
+pub struct WorldState {
+    step: StepState,
+    bodies: Vec<SavedBody>,
+    joints: Vec<(JointHandle, JointState)>,
+    contacts: Vec<ContactRecord<(BodyHandle, FixtureHandle)>>,
+    proxy_tree: ProxyTreeState<(BodyHandle, FixtureHandle)>,
+}
+
+#[derive(Clone, Debug)]
+struct SavedBody {
+    handle: BodyHandle,
+    body_type: BodyType,
+    active: bool,
+    fixtures: Vec<FixtureHandle>,
+    state: BodyState,
+}
+
+impl<U: UserDataTypes> World<U> {
+    /// Saves the motion of the bodies along with the solver state of the joints
+    /// and contacts, for `restore_state` to go back to it.
+    pub fn save_state(&self) -> WorldState {
+        let mut fixture_refs = HashMap::new();
+        let bodies = self.bodies
+            .iter()
+            .map(|(handle, body)| {
+                let body = body.borrow();
+                let fixtures = body.fixtures()
+                    .map(|(fixture_handle, fixture)| {
+                        let ptr = unsafe { fixture.borrow().ptr() };
+                        fixture_refs.insert(ptr, (handle, fixture_handle));
+                        fixture_handle
+                    })
+                    .collect();
+                SavedBody {
+                    handle: handle,
+                    body_type: body.body_type(),
+                    active: body.is_active(),
+                    fixtures: fixtures,
+                    state: BodyState::take(&body),
+                }
+            })
+            .collect();
+
+        let joints = self.joints
+            .iter()
+            .map(|(handle, joint)| (handle, JointState::take(&joint.borrow())))
+            .collect();
+
+        unsafe {
+            WorldState {
+                step: StepState::take(self),
+                bodies: bodies,
+                joints: joints,
+                contacts: ContactRecord::take_all(self.ptr(), |f| fixture_refs[&f]),
+                proxy_tree: ProxyTreeState::take(self.ptr(), |f| fixture_refs[&f]),
+            }
+        }
+    }
+
+    /// Goes back to a state saved by `save_state`, in place. No body, fixture
+    /// or joint may have been created or destroyed since, nor any body type or
+    /// activity changed, otherwise `Error::SnapshotMismatch` is returned and
+    /// the world is left untouched. The contact listener is not called.
+    pub fn restore_state(&mut self, state: &WorldState) -> Result<(), Error> {
+        self.check_unlocked()?;
+        self.check_topology(state)?;
+        unsafe {
+            let fixture_ptr = |fixture: &_| self.fixture_ptr(fixture);
+            ContactRecord::check_all(&state.contacts, &fixture_ptr)?;
+            state.proxy_tree.check(self.ptr(), &fixture_ptr)?;
+        }
+
+        for saved in &state.bodies {
+            saved.state.restore(&mut self.body_mut(saved.handle));
+        }
+        for &(handle, ref joint_state) in &state.joints {
+            joint_state.restore(&mut self.joint_mut(handle));
+        }
+
+        unsafe {
+            let world_ptr = self.mut_ptr();
+            let fixture_ptr = |fixture: &_| self.fixture_ptr(fixture);
+            ContactRecord::restore_all(world_ptr, &state.contacts, &fixture_ptr)?;
+            state.proxy_tree.restore(world_ptr, &fixture_ptr)?;
+        }
+        state.step.restore(self);
+        Ok(())
+    }
+
+    fn check_topology(&self, state: &WorldState) -> Result<(), Error> {
+        if self.body_count() as usize != state.bodies.len() ||
+           self.joint_count() as usize != state.joints.len() {
+            return Err(Error::SnapshotMismatch);
+        }
+
+        for saved in &state.bodies {
+            let body = match self.bodies.get(saved.handle) {
+                Some(body) => body,
+                None => return Err(Error::SnapshotMismatch),
+            };
+            if body.body_type() != saved.body_type || body.is_active() != saved.active ||
+               body.fixtures().count() != saved.fixtures.len() ||
+               saved.fixtures.iter().any(|&f| body.try_fixture(f).is_err()) {
+                return Err(Error::SnapshotMismatch);
+            }
+        }
+
+        if state.joints.iter().any(|&(handle, _)| !self.joints.is_valid(handle)) {
+            return Err(Error::SnapshotMismatch);
+        }
+        Ok(())
+    }
+
+    fn fixture_ptr(&self,
+                   &(body, fixture): &(BodyHandle, FixtureHandle))
+                   -> Option<*mut ffi::Fixture> {
+        let body = self.try_body(body).ok()?;
+        let fixture = body.try_fixture(fixture).ok()?;
+        Some(unsafe { fixture.ptr() as *mut ffi::Fixture })
+    }
+}
+
+/// The items of a Box2D list, which is built by prepending, in creation order.
+#[doc(hidden)]
+pub unsafe fn creation_order<T>(first: *const T,
+                                next: unsafe extern "C" fn(*const T) -> *const T)
+                                -> Vec<*const T> {
+    let mut items = Vec::new();
+    let mut item = first;
+    while !item.is_null() {
+        items.push(item);
+        item = next(item);
+    }
+    items.reverse();
+    items
+}
+
+/// The `b2_nullNode` of the broad-phase tree, also its `e_nullProxy`.
+const NULL_NODE: i32 = -1;
+
+/// Whether the fixture has a proxy for `child`, which is only the case while
+/// its body is active.
+unsafe fn has_proxy(fixture: *mut ffi::Fixture, child: i32) -> bool {
+    0 <= child && child < ffi::Fixture_get_proxy_count(fixture)
+}
+
+/// Whether Box2D has a contact type for the shapes of the fixtures, there is
+/// none between edges and chains.
+unsafe fn can_collide(fixture_a: *mut ffi::Fixture, fixture_b: *mut ffi::Fixture) -> bool {
+    let solid = |fixture| match ffi::Fixture_get_type(fixture) {
+        ShapeType::Circle | ShapeType::Polygon => true,
+        _ => false,
+    };
+    solid(fixture_a) || solid(fixture_b)
+}
+
+/// The time step state of the world and its flags.
+#[repr(C)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct StepState {
+    pub flags: i32,
+    pub inv_dt0: f32,
+    pub step_complete: bool,
+}
+
+impl StepState {
+    pub fn take<U: UserDataTypes>(world: &World<U>) -> Self {
+        unsafe {
+            let mut state = mem::MaybeUninit::uninit();
+            ffi::World_get_exact_state(world.ptr(), state.as_mut_ptr());
+            state.assume_init()
+        }
+    }
+
+    pub fn restore<U: UserDataTypes>(&self, world: &mut World<U>) {
+        unsafe { ffi::World_set_exact_state(world.mut_ptr(), self) }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct BodyState {
+    pub position: Vec2,
+    /// Sine and cosine of the angle.
+    pub rotation: [f32; 2],
+    pub local_center: Vec2,
+    pub c0: Vec2,
+    pub c: Vec2,
+    pub a0: f32,
+    pub a: f32,
+    pub alpha0: f32,
+    pub linear_velocity: Vec2,
+    pub angular_velocity: f32,
+    pub force: Vec2,
+    pub torque: f32,
+    pub mass: f32,
+    pub inv_mass: f32,
+    pub inertia: f32,
+    pub inv_inertia: f32,
+    pub sleep_time: f32,
+    pub flags: u16,
+}
+
+impl BodyState {
+    pub fn take(body: &Body) -> Self {
+        unsafe {
+            let mut state = mem::MaybeUninit::uninit();
+            ffi::Body_get_exact_state(body.ptr(), state.as_mut_ptr());
+            state.assume_init()
+        }
+    }
+
+    pub fn restore(&self, body: &mut Body) {
+        unsafe { ffi::Body_set_exact_state(body.mut_ptr(), self) }
+    }
+}
+
+/// The warm starting impulses of a joint, their meaning depends on its type.
+#[repr(C)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct JointState {
+    pub impulse: [f32; 4],
+    pub limit_state: i32,
+}
+
+impl JointState {
+    pub fn take(joint: &UnknownJoint) -> Self {
+        unsafe {
+            let mut state = mem::MaybeUninit::uninit();
+            ffi::Joint_get_exact_state(joint.base_ptr(), state.as_mut_ptr());
+            state.assume_init()
+        }
+    }
+
+    pub fn restore(&self, joint: &mut UnknownJoint) {
+        unsafe { ffi::Joint_set_exact_state(joint.mut_base_ptr(), self) }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct ManifoldPointState {
+    pub local_point: Vec2,
+    pub normal_impulse: f32,
+    pub tangent_impulse: f32,
+    /// The key of the `ContactId`.
+    pub id: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct ContactState {
+    pub points: [ManifoldPointState; MAX_MANIFOLD_POINTS],
+    pub local_normal: Vec2,
+    pub local_point: Vec2,
+    pub manifold_type: i32,
+    pub point_count: i32,
+    pub flags: u32,
+    pub toi_count: i32,
+    pub toi: f32,
+    pub friction: f32,
+    pub restitution: f32,
+    pub tangent_speed: f32,
+}
+
+/// A contact between the fixtures referred to by `F`.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct ContactRecord<F> {
+    pub fixture_a: F,
+    pub child_a: i32,
+    pub fixture_b: F,
+    pub child_b: i32,
+    pub state: ContactState,
+}
+
+impl<F> ContactRecord<F> {
+    /// The contacts of the world in creation order.
+    #[doc(hidden)]
+    pub unsafe fn take_all<R>(world: *const ffi::World, fixture_ref: R) -> Vec<Self>
+        where R: Fn(*const ffi::Fixture) -> F
+    {
+        creation_order(ffi::World_get_contact_list_const(world), ffi::Contact_get_next_const)
+            .into_iter()
+            .map(|contact| {
+                let mut state = mem::MaybeUninit::uninit();
+                ffi::Contact_get_exact_state(contact, state.as_mut_ptr());
+                ContactRecord {
+                    fixture_a: fixture_ref(ffi::Contact_get_fixture_a_const(contact)),
+                    child_a: ffi::Contact_get_child_index_a(contact),
+                    fixture_b: fixture_ref(ffi::Contact_get_fixture_b_const(contact)),
+                    child_b: ffi::Contact_get_child_index_b(contact),
+                    state: state.assume_init(),
+                }
+            })
+            .collect()
+    }
+
+    /// Checks that `restore_all` can recreate every contact.
+    #[doc(hidden)]
+    pub unsafe fn check_all<P>(contacts: &[Self], fixture_ptr: P) -> Result<(), Error>
+        where P: Fn(&F) -> Option<*mut ffi::Fixture>
+    {
+        for contact in contacts {
+            contact.fixtures(&fixture_ptr)?;
+            let state = &contact.state;
+            if state.point_count < 0 || state.point_count > MAX_MANIFOLD_POINTS as i32 ||
+               state.manifold_type < 0 || state.manifold_type > 2 {
+                return Err(Error::SnapshotMismatch);
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces the contacts of the world, which are left untouched on error.
+    #[doc(hidden)]
+    pub unsafe fn restore_all<P>(world: *mut ffi::World,
+                                 contacts: &[Self],
+                                 fixture_ptr: P)
+                                 -> Result<(), Error>
+        where P: Fn(&F) -> Option<*mut ffi::Fixture>
+    {
+        Self::check_all(contacts, &fixture_ptr)?;
+        ffi::World_clear_contacts(world);
+        for contact in contacts {
+            let (fixture_a, fixture_b) = contact.fixtures(&fixture_ptr)?;
+            let ptr = ffi::World_create_exact_contact(world,
+                                                      fixture_a,
+                                                      contact.child_a,
+                                                      fixture_b,
+                                                      contact.child_b);
+            // the shape types are checked above
+            assert!(!ptr.is_null(), "contact between fixtures that cannot collide");
+            ffi::Contact_set_exact_state(ptr, &contact.state);
+        }
+        Ok(())
+    }
+
+    unsafe fn fixtures<P>(&self,
+                          fixture_ptr: P)
+                          -> Result<(*mut ffi::Fixture, *mut ffi::Fixture), Error>
+        where P: Fn(&F) -> Option<*mut ffi::Fixture>
+    {
+        match (fixture_ptr(&self.fixture_a), fixture_ptr(&self.fixture_b)) {
+            (Some(a), Some(b)) if has_proxy(a, self.child_a) && has_proxy(b, self.child_b) &&
+                                  can_collide(a, b) => Ok((a, b)),
+            _ => Err(Error::SnapshotMismatch),
+        }
+    }
+}
+
+/// The dynamic tree of the broad-phase, which decides the order new contacts
+/// are found in, its leaves referring to fixtures with `F`.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct ProxyTreeState<F> {
+    pub root: i32,
+    pub node_count: i32,
+    pub free_list: i32,
+    pub path: u32,
+    pub insertion_count: i32,
+    pub proxy_count: i32,
+    pub nodes: Vec<TreeNodeState<F>>,
+    /// Proxies moved since the last step.
+    pub moves: Vec<i32>,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct TreeNodeState<F> {
+    pub lower: Vec2,
+    pub upper: Vec2,
+    /// The fixture and child index of a leaf.
+    pub proxy: Option<(F, i32)>,
+    pub parent_or_next: i32,
+    pub child1: i32,
+    pub child2: i32,
+    pub height: i32,
+}
+
+impl<F> ProxyTreeState<F> {
+    #[doc(hidden)]
+    pub unsafe fn take<R>(world: *const ffi::World, fixture_ref: R) -> Self
+        where R: Fn(*const ffi::Fixture) -> F
+    {
+        let mut info = mem::MaybeUninit::uninit();
+        ffi::World_get_proxy_tree_info(world, info.as_mut_ptr());
+        let info: ffi::ProxyTreeInfo = info.assume_init();
+
+        let mut nodes: Vec<ffi::ProxyTreeNode> = (0..info.node_capacity)
+            .map(|_| mem::zeroed())
+            .collect();
+        let mut moves = vec![0; info.move_count as usize];
+        ffi::World_get_proxy_tree(world, nodes.as_mut_ptr(), moves.as_mut_ptr());
+
+        ProxyTreeState {
+            root: info.root,
+            node_count: info.node_count,
+            free_list: info.free_list,
+            path: info.path,
+            insertion_count: info.insertion_count,
+            proxy_count: info.proxy_count,
+            nodes: nodes.iter()
+                .map(|node| {
+                    TreeNodeState {
+                        lower: node.aabb.lower,
+                        upper: node.aabb.upper,
+                        proxy: if node.fixture.is_null() {
+                            None
+                        } else {
+                            Some((fixture_ref(node.fixture), node.child_index))
+                        },
+                        parent_or_next: node.parent_or_next,
+                        child1: node.child1,
+                        child2: node.child2,
+                        height: node.height,
+                    }
+                })
+                .collect(),
+            moves: moves,
+        }
+    }
+
+    /// Checks that every node id is in the tree and that the leaves are the
+    /// proxies of the fixtures of the world, each exactly once.
+    #[doc(hidden)]
+    pub unsafe fn check<P>(&self, world: *const ffi::World, fixture_ptr: P) -> Result<(), Error>
+        where P: Fn(&F) -> Option<*mut ffi::Fixture>
+    {
+        self.nodes(world, fixture_ptr).map(|_| ())
+    }
+
+    /// The nodes to give Box2D, once checked.
+    unsafe fn nodes<P>(&self, world: *const ffi::World, fixture_ptr: P)
+                       -> Result<Vec<ffi::ProxyTreeNode>, Error>
+        where P: Fn(&F) -> Option<*mut ffi::Fixture>
+    {
+        let capacity = self.nodes.len();
+        let in_tree = |id: i32| id == NULL_NODE || (0 <= id && (id as usize) < capacity);
+        if capacity > i32::max_value() as usize || self.node_count < 0 ||
+           self.node_count as usize > capacity || !in_tree(self.root) ||
+           !in_tree(self.free_list) {
+            return Err(Error::SnapshotMismatch);
+        }
+
+        let mut proxies = HashSet::new();
+        let mut nodes = Vec::with_capacity(capacity);
+        for node in &self.nodes {
+            if !in_tree(node.parent_or_next) || !in_tree(node.child1) || !in_tree(node.child2) {
+                return Err(Error::SnapshotMismatch);
+            }
+            // queries take the user data of every leaf for a proxy
+            let leaf = node.height >= 0 && node.child1 == NULL_NODE;
+            let (fixture, child_index) = match node.proxy {
+                Some((ref fixture, child_index)) if leaf => {
+                    match fixture_ptr(fixture) {
+                        Some(fixture) if has_proxy(fixture, child_index) &&
+                                         proxies.insert((fixture, child_index)) => {
+                            (fixture as *const _, child_index)
+                        }
+                        _ => return Err(Error::SnapshotMismatch),
+                    }
+                }
+                None if !leaf => (ptr::null(), 0),
+                _ => return Err(Error::SnapshotMismatch),
+            };
+            nodes.push(ffi::ProxyTreeNode {
+                aabb: AABB {
+                    lower: node.lower,
+                    upper: node.upper,
+                },
+                fixture: fixture,
+                child_index: child_index,
+                parent_or_next: node.parent_or_next,
+                child1: node.child1,
+                child2: node.child2,
+                height: node.height,
+            });
+        }
+
+        // no proxy may be left pointing to a node of the replaced tree
+        let mut info = mem::MaybeUninit::uninit();
+        ffi::World_get_proxy_tree_info(world, info.as_mut_ptr());
+        let info: ffi::ProxyTreeInfo = info.assume_init();
+        if self.proxy_count != info.proxy_count || proxies.len() != info.proxy_count as usize {
+            return Err(Error::SnapshotMismatch);
+        }
+
+        let is_proxy = |id: i32| {
+            id == NULL_NODE || (in_tree(id) && !nodes[id as usize].fixture.is_null())
+        };
+        if !self.moves.iter().all(|&id| is_proxy(id)) {
+            return Err(Error::SnapshotMismatch);
+        }
+        Ok(nodes)
+    }
+
+    /// Replaces the tree of the world, which is left untouched on error.
+    #[doc(hidden)]
+    pub unsafe fn restore<P>(&self, world: *mut ffi::World, fixture_ptr: P) -> Result<(), Error>
+        where P: Fn(&F) -> Option<*mut ffi::Fixture>
+    {
+        let nodes = self.nodes(world, fixture_ptr)?;
+        let info = ffi::ProxyTreeInfo {
+            root: self.root,
+            node_count: self.node_count,
+            node_capacity: self.nodes.len() as i32,
+            free_list: self.free_list,
+            path: self.path,
+            insertion_count: self.insertion_count,
+            proxy_count: self.proxy_count,
+            move_count: self.moves.len() as i32,
+        };
+
+        ffi::World_set_proxy_tree(world, &info, nodes.as_ptr(), self.moves.as_ptr());
+        Ok(())
+    }
+}
+
+#[doc(hidden)]
+pub mod ffi {
+    pub use dynamics::world::ffi::{World, World_get_body_list_const, World_get_joint_list_const,
+                                   World_get_contact_list_const};
+    pub use dynamics::body::ffi::{Body, Body_get_next_const, Body_get_fixture_list_const};
+    pub use dynamics::fixture::ffi::{Fixture, Fixture_get_next_const, Fixture_get_type};
+    pub use dynamics::joints::ffi::{Joint, Joint_get_next_const};
+    pub use dynamics::contacts::ffi::{Contact, Contact_get_next_const,
+                                      Contact_get_fixture_a_const, Contact_get_child_index_a,
+                                      Contact_get_fixture_b_const, Contact_get_child_index_b};
+    use collision::AABB;
+    use super::{StepState, BodyState, JointState, ContactState};
+
+    #[repr(C)]
+    pub struct ProxyTreeInfo {
+        pub root: i32,
+        pub node_count: i32,
+        pub node_capacity: i32,
+        pub free_list: i32,
+        pub path: u32,
+        pub insertion_count: i32,
+        pub proxy_count: i32,
+        pub move_count: i32,
+    }
+
+    #[repr(C)]
+    pub struct ProxyTreeNode {
+        pub aabb: AABB,
+        pub fixture: *const Fixture,
+        pub child_index: i32,
+        pub parent_or_next: i32,
+        pub child1: i32,
+        pub child2: i32,
+        pub height: i32,
+    }
+
+    extern "C" {
+        pub fn World_get_exact_state(slf: *const World, state: *mut StepState);
+        pub fn World_set_exact_state(slf: *mut World, state: *const StepState);
+        pub fn Body_get_exact_state(slf: *const Body, state: *mut BodyState);
+        pub fn Body_set_exact_state(slf: *mut Body, state: *const BodyState);
+        pub fn Joint_get_exact_state(slf: *const Joint, state: *mut JointState);
+        pub fn Joint_set_exact_state(slf: *mut Joint, state: *const JointState);
+        pub fn Contact_get_exact_state(slf: *const Contact, state: *mut ContactState);
+        pub fn Contact_set_exact_state(slf: *mut Contact, state: *const ContactState);
+        pub fn Fixture_get_proxy_count(slf: *const Fixture) -> i32;
+        pub fn World_clear_contacts(slf: *mut World);
+        pub fn World_create_exact_contact(slf: *mut World,
+                                          fixture_a: *mut Fixture,
+                                          index_a: i32,
+                                          fixture_b: *mut Fixture,
+                                          index_b: i32)
+                                          -> *mut Contact;
+        pub fn World_get_proxy_tree_info(slf: *const World, info: *mut ProxyTreeInfo);
+        pub fn World_get_proxy_tree(slf: *const World, nodes: *mut ProxyTreeNode, moves: *mut i32);
+        pub fn World_set_proxy_tree(slf: *mut World,
+                                    info: *const ProxyTreeInfo,
+                                    nodes: *const ProxyTreeNode,
+                                    moves: *const i32);
+    }
+}
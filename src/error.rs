@@ -0,0 +1,52 @@
+use std::fmt;
+use std::error;
+use collision::shapes::ShapeError;
+use dynamics::joints::JointDefError;
+use dynamics::world::WorldLocked;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Error {
+    /// The handle does not refer to a living body, fixture or joint.
+    InvalidHandle,
+    /// The body, fixture or joint is already borrowed incompatibly.
+    AlreadyBorrowed,
+    /// The world is locked during a step, it cannot be modified from callbacks.
+    WorldLocked,
+    InvalidShape(ShapeError),
+    InvalidJointDef(JointDefError),
+    /// The snapshot does not match the world or contains duplicate ids.
+    SnapshotMismatch,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::InvalidHandle => write!(f, "invalid handle"),
+            Error::AlreadyBorrowed => write!(f, "already borrowed"),
+            Error::WorldLocked => write!(f, "the world is locked"),
+            Error::InvalidShape(ref e) => write!(f, "invalid shape: {}", e),
+            Error::InvalidJointDef(ref e) => write!(f, "invalid joint definition: {}", e),
+            Error::SnapshotMismatch => write!(f, "snapshot mismatch"),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl From<WorldLocked> for Error {
+    fn from(_: WorldLocked) -> Error {
+        Error::WorldLocked
+    }
+}
+
+impl From<ShapeError> for Error {
+    fn from(e: ShapeError) -> Error {
+        Error::InvalidShape(e)
+    }
+}
+
+impl From<JointDefError> for Error {
+    fn from(e: JointDefError) -> Error {
+        Error::InvalidJointDef(e)
+    }
+}
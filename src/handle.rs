@@ -4,6 +4,7 @@ use std::iter::{Iterator, DoubleEndedIterator};
 use vec_map::{self, VecMap};
 use std::mem;
 use std::fmt;
+use error::Error;
 
 pub struct TypedHandle<T: ?Sized> {
     index: usize,
@@ -74,6 +75,17 @@ impl<E> HandleEntry<E> {
     }
 }
 
+/// Which slots of a `HandleMap` are free and the versions of all its slots,
+/// enough for handles to be given out the same way again.
+#[doc(hidden)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct HandleLayout {
+    pub next_index: usize,
+    pub availables: Vec<usize>,
+    pub versions: Vec<usize>,
+}
+
 #[doc(hidden)]
 pub struct HandleMap<E, T: ?Sized = ()> {
     next_index: usize,
@@ -122,19 +134,76 @@ impl<T: ?Sized, E> HandleMap<E, T> {
     }
 
     fn find_available(&mut self) -> usize {
-        let index = match self.availables.pop() {
-            Some(index) => index,
-            None => {
-                let next = self.next_index + 1;
-                mem::replace(&mut self.next_index, next)
+        loop {
+            let index = match self.availables.pop() {
+                Some(index) => index,
+                None => {
+                    let next = self.next_index + 1;
+                    mem::replace(&mut self.next_index, next)
+                }
+            };
+
+            match self.entries.get(index) {
+                // never hand out a live slot twice
+                Some(entry) if entry.inner.is_some() => continue,
+                Some(_) => {}
+                None => {
+                    self.entries.insert(index, HandleEntry::new());
+                }
             }
-        };
+            return index;
+        }
+    }
+
+    /// Makes the next insertion return `handle`, whose slot must be free.
+    pub fn reserve_next(&mut self, handle: TypedHandle<T>) -> Result<(), Error> {
+        let index = handle.index;
+        if self.entries.get(index).map_or(false, |e| e.inner.is_some()) {
+            return Err(Error::InvalidHandle);
+        }
 
-        if self.entries.len() <= index {
-            self.entries.insert(index, HandleEntry::new());
+        self.entries.entry(index).or_insert_with(HandleEntry::new).version = handle.version;
+        if index >= self.next_index {
+            // the skipped slots stay available to later insertions
+            self.availables.extend(self.next_index..index);
+            self.next_index = index + 1;
         }
+        self.availables.retain(|&i| i != index);
+        self.availables.push(index);
+        Ok(())
+    }
 
-        index
+    pub fn layout(&self) -> HandleLayout {
+        HandleLayout {
+            next_index: self.next_index,
+            availables: self.availables.clone(),
+            versions: (0..self.next_index)
+                .map(|i| self.entries.get(i).map_or(0, |e| e.version))
+                .collect(),
+        }
+    }
+
+    /// Frees the slots and sets the versions of `layout`, keeping the values
+    /// in the slots it has as occupied.
+    pub fn set_layout(&mut self, layout: &HandleLayout) -> Result<(), Error> {
+        if layout.versions.len() != layout.next_index {
+            return Err(Error::InvalidHandle);
+        }
+        let mismatch = self.entries.iter().any(|(index, entry)| {
+            entry.inner.is_some() &&
+            (index >= layout.next_index || layout.availables.contains(&index) ||
+             entry.version != layout.versions[index])
+        });
+        if mismatch {
+            return Err(Error::InvalidHandle);
+        }
+
+        for (index, &version) in layout.versions.iter().enumerate() {
+            self.entries.entry(index).or_insert_with(HandleEntry::new).version = version;
+        }
+        self.next_index = layout.next_index;
+        self.availables = layout.availables.clone();
+        Ok(())
     }
 
     pub fn remove(&mut self, handle: TypedHandle<T>) -> E {
@@ -158,20 +227,22 @@ impl<T: ?Sized, E> HandleMap<E, T> {
         self.entries.capacity()
     }
 
-    pub fn is_valid(&self, handle: TypedHandle<T>) -> bool {
-        let entry = &self.entries[handle.index];
+    pub fn try_remove(&mut self, handle: TypedHandle<T>) -> Result<E, Error> {
+        if !self.is_valid(handle) {
+            return Err(Error::InvalidHandle);
+        }
+        Ok(self.remove(handle))
+    }
 
-        entry.version == handle.version && entry.inner.is_some()
+    pub fn is_valid(&self, handle: TypedHandle<T>) -> bool {
+        self.get_inner(handle).is_some()
     }
 
     #[inline]
     fn get_inner(&self, handle: TypedHandle<T>) -> Option<&RefCell<E>> {
-        let entry = &self.entries[handle.index];
-
-        if entry.version == handle.version {
-            entry.inner.as_ref()
-        } else {
-            None
+        match self.entries.get(handle.index) {
+            Some(entry) if entry.version == handle.version => entry.inner.as_ref(),
+            _ => None,
         }
     }
 
@@ -183,6 +254,16 @@ impl<T: ?Sized, E> HandleMap<E, T> {
         self.get_inner(handle).map(|e| e.borrow_mut())
     }
 
+    pub fn try_get(&self, handle: TypedHandle<T>) -> Result<Ref<E>, Error> {
+        let inner = self.get_inner(handle).ok_or(Error::InvalidHandle)?;
+        inner.try_borrow().map_err(|_| Error::AlreadyBorrowed)
+    }
+
+    pub fn try_get_mut(&self, handle: TypedHandle<T>) -> Result<RefMut<E>, Error> {
+        let inner = self.get_inner(handle).ok_or(Error::InvalidHandle)?;
+        inner.try_borrow_mut().map_err(|_| Error::AlreadyBorrowed)
+    }
+
     pub fn iter<'a>(&'a self) -> HandleIter<'a, T, E> {
         HandleIter {
             iter: self.entries.iter(),
@@ -191,6 +272,18 @@ impl<T: ?Sized, E> HandleMap<E, T> {
     }
 }
 
+impl<T: ?Sized, E> IntoIterator for HandleMap<E, T> {
+    type Item = (TypedHandle<T>, E);
+    type IntoIter = HandleIntoIter<T, E>;
+
+    fn into_iter(self) -> HandleIntoIter<T, E> {
+        HandleIntoIter {
+            iter: self.entries.into_iter(),
+            phantom: PhantomData,
+        }
+    }
+}
+
 impl<'a, T: ?Sized, E> IntoIterator for &'a HandleMap<E, T> {
     type Item = (TypedHandle<T>, &'a RefCell<E>);
     type IntoIter = HandleIter<'a, T, E>;
@@ -260,6 +353,30 @@ double_ended_iterator! {
     as_ref and then
 }
 
+pub struct HandleIntoIter<T: ?Sized, E> {
+    iter: vec_map::IntoIter<HandleEntry<E>>,
+    phantom: PhantomData<T>,
+}
+
+impl<T: ?Sized, E> Iterator for HandleIntoIter<T, E> {
+    type Item = (TypedHandle<T>, E);
+
+    #[inline]
+    fn next(&mut self) -> Option<(TypedHandle<T>, E)> {
+        while let Some((index, entry)) = self.iter.next() {
+            if let Some(inner) = entry.inner {
+                return Some((TypedHandle::new(index, entry.version), inner.into_inner()));
+            }
+        }
+        None
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -306,6 +423,102 @@ mod test {
         assert!(map.get(handle).is_none())
     }
 
+    #[test]
+    fn into_iter_skips_removed() {
+        let mut map = HandleMap::<usize>::new();
+
+        let a = map.insert(DUMMY_VALUE);
+        let b = map.insert(DUMMY_VALUE + 1);
+        map.remove(a);
+
+        let values: Vec<_> = map.into_iter().collect();
+        assert_eq!(values, vec![(b, DUMMY_VALUE + 1)]);
+    }
+
+    #[test]
+    fn try_get_errors() {
+        let mut map = HandleMap::<usize>::new();
+
+        let handle = map.insert(DUMMY_VALUE);
+        {
+            let _borrowed = map.get_mut(handle);
+            assert_eq!(map.try_get(handle).err(), Some(Error::AlreadyBorrowed));
+        }
+        map.remove(handle);
+
+        assert_eq!(map.try_get(handle).err(), Some(Error::InvalidHandle));
+        assert_eq!(map.try_remove(handle).err(), Some(Error::InvalidHandle));
+    }
+
+    #[test]
+    fn same_handles_after_layout() {
+        let mut map = HandleMap::<usize>::new();
+        let a = map.insert(DUMMY_VALUE);
+        let b = map.insert(DUMMY_VALUE);
+        map.remove(a);
+        let layout = map.layout();
+
+        let mut copy = HandleMap::<usize>::new();
+        copy.reserve_next(b).unwrap();
+        assert_eq!(copy.insert(DUMMY_VALUE), b);
+        assert_eq!(copy.reserve_next(b).err(), Some(Error::InvalidHandle));
+        copy.set_layout(&layout).unwrap();
+
+        assert_eq!(copy.layout(), layout);
+        assert_eq!(copy.insert(DUMMY_VALUE), map.insert(DUMMY_VALUE));
+        assert_eq!(copy.insert(DUMMY_VALUE), map.insert(DUMMY_VALUE));
+    }
+
+    #[test]
+    fn set_short_layout() {
+        let mut map = HandleMap::<usize>::new();
+        map.insert(DUMMY_VALUE);
+        map.insert(DUMMY_VALUE);
+        let layout = HandleLayout {
+            next_index: 2,
+            availables: Vec::new(),
+            versions: vec![0],
+        };
+
+        assert_eq!(map.set_layout(&layout).err(), Some(Error::InvalidHandle));
+    }
+
+    #[test]
+    fn reserve_next_in_sparse_map() {
+        let mut map = HandleMap::<usize>::new();
+        let a = map.insert(DUMMY_VALUE);
+        let b = map.insert(DUMMY_VALUE);
+        map.remove(a);
+        map.remove(b);
+        let handle = map.insert(DUMMY_VALUE);
+        assert_eq!((handle.index(), handle.version()), (1, 1));
+
+        // only the slot of `handle` is in the copy
+        let mut copy = HandleMap::<usize>::new();
+        copy.reserve_next(handle).unwrap();
+        assert_eq!(copy.insert(DUMMY_VALUE), handle);
+    }
+
+    #[test]
+    fn inserts_after_reserving_high_index() {
+        let mut map = HandleMap::<usize>::new();
+        let first = map.insert(DUMMY_VALUE);
+        let reserved = TypedHandle::new(4, 2);
+        map.reserve_next(reserved).unwrap();
+        assert_eq!(map.insert(DUMMY_VALUE + 1), reserved);
+
+        let mut handles = vec![first, reserved];
+        for i in 0..6 {
+            handles.push(map.insert(i));
+        }
+        let mut indices: Vec<_> = handles.iter().map(|h| h.index()).collect();
+        indices.sort();
+        indices.dedup();
+        assert_eq!(indices.len(), handles.len());
+        assert_eq!(*map.get(reserved).unwrap(), DUMMY_VALUE + 1);
+        assert_eq!(*map.get(first).unwrap(), DUMMY_VALUE);
+    }
+
     #[test]
     #[should_panic]
     fn remove_with_old_handle() {
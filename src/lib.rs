@@ -38,6 +38,27 @@
 //! let fixture = body.fixture(handle);
 //! ```
 //!
+//! # Errors
+//!
+//! Accessors like `World::body` panic on a stale handle or a conflicting borrow.
+//! Their `try_*` counterparts return an `Error` instead, which tells the failures
+//! apart.
+//!
+//! Since 0.5 these are `Result`s: `World::try_body`, `try_body_mut`, `try_joint`,
+//! `try_joint_mut`, `try_create_joint`, `try_create_joint_with`,
+//! `MetaBody::try_fixture`, `try_fixture_mut`, `JointDef::try_create` and the
+//! `try_init` of the joint definitions returned an `Option` before.
+//!
+//! ```
+//! # use wrapped2d::b2;
+//! # use wrapped2d::user_data::NoUserData;
+//! # let gravity = b2::Vec2 { x: 0., y: -10. };
+//! # let mut world = b2::World::<NoUserData>::new(&gravity);
+//! let handle = world.create_body(&b2::BodyDef::new());
+//! world.destroy_body(handle);
+//! assert_eq!(world.try_body(handle).err(), Some(wrapped2d::Error::InvalidHandle));
+//! ```
+//!
 //! # User Data
 //!
 //! You can provide a unit struct to specify the user data types that will be used for bodies,
@@ -104,6 +125,9 @@ pub mod common;
 pub mod collision;
 pub mod dynamics;
 pub mod user_data;
+pub mod error;
+
+pub use error::Error;
 #[cfg(feature = "serialize")]
 pub mod serialize;
 
@@ -116,13 +140,21 @@ pub mod b2 {
     pub use collision::{AABB, ContactFeature, ContactId, Manifold, ManifoldPoint, WorldManifold,
                         RayCastInput, RayCastOutput, ContactFeatureType, ManifoldType, PointState,
                         get_point_states, test_overlap, distance, time_of_impact};
-    pub use collision::shapes::{MassData, ShapeType, UnknownShape, Shape, ChainShape, CircleShape,
-                                EdgeShape, PolygonShape};
+    pub use collision::shapes::{MassData, ShapeError, ShapeType, UnknownShape, Shape, ChainShape,
+                                CircleShape, EdgeShape, PolygonShape, CompoundShape,
+                                ShapeDesc};
+    pub use collision::shapes::rounded::{Capsule, RoundedBox, ShapeGroup};
     pub use dynamics::Profile;
-    pub use dynamics::world::{World, BodyHandle, JointHandle};
-    pub use dynamics::world::callbacks::{ContactImpulse, ContactFilter, ContactListener,
-                                         QueryCallback, RayCastCallback};
-    pub use dynamics::body::{Body, BodyDef, MetaBody, BodyType, FixtureHandle};
+    pub use dynamics::stepper::Stepper;
+    pub use dynamics::world::{World, BodyHandle, JointHandle, WorldLocked};
+    pub use dynamics::world::queries::{QueryFilter, RayCastHit};
+    pub use dynamics::world::commands::WorldCommands;
+    pub use dynamics::world::state::WorldState;
+    pub use dynamics::world::callbacks::{ContactAccess, ContactImpulse, ContactFilter,
+                                         ContactListener, ContactEvent, ContactEventKind,
+                                         ContactEventQueue, ContactPoints, DestructionListener, QueryCallback,
+                                         RayCastCallback, ShapeQueryCallback};
+    pub use dynamics::body::{Body, BodyDef, MetaBody, BodyType, FixtureHandle, FixtureGroup};
     pub use dynamics::fixture::{Filter, Fixture, FixtureDef, MetaFixture};
     pub use dynamics::joints::{DistanceJoint, DistanceJointDef, FrictionJoint, FrictionJointDef,
                                GearJoint, GearJointDef, MetaJoint, MotorJoint,
@@ -130,5 +162,5 @@ pub mod b2 {
                                PrismaticJointDef, PulleyJoint, PulleyJointDef, RevoluteJoint,
                                RevoluteJointDef, RopeJoint, RopeJointDef, WeldJoint, WeldJointDef,
                                WheelJoint, WheelJointDef, JointType, LimitState, UnknownJoint,
-                               Joint, JointDef};
+                               Joint, JointDef, JointDefError};
 }
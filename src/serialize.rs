@@ -1,10 +1,20 @@
 use std::collections::HashMap;
+use std::collections::hash_map::Entry;
 use std::fmt::Debug;
 use serde::ser::{Serialize, Serializer};
 use serde::de::{Deserialize, Deserializer, DeserializeOwned};
 
 #[doc(hidden)] pub use b2::*;
 use user_data::{UserDataTypes, UserData};
+use handle::{TypedHandle, HandleLayout};
+use error::Error;
+
+#[path = "serialize_exact.rs"]
+pub mod exact;
+#[path = "serialize_rube.rs"]
+pub mod rube;
+
+pub use self::exact::ExactWorldSnapshot;
 
 impl Serialize for Vec2 {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -24,13 +34,34 @@ impl<'de> Deserialize<'de> for Vec2 {
     }
 }
 
+impl<T: ?Sized> Serialize for TypedHandle<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        (self.index(), self.version()).serialize(serializer)
+    }
+}
+
+impl<'de, T: ?Sized> Deserialize<'de> for TypedHandle<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        let (index, version) = Deserialize::deserialize(deserializer)?;
+        Ok(TypedHandle::new(index, version))
+    }
+}
+
 #[derive(Clone, Copy, Hash, Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub struct BodyId(pub usize);
 #[derive(Clone, Copy, Hash, Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub struct JointId(pub usize);
+/// A fixture of the body `.0`.
+#[derive(Clone, Copy, Hash, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct FixtureId(pub BodyId, pub usize);
 
 pub struct IdToHandle {
     bodies: HashMap<BodyId, BodyHandle>,
+    fixtures: HashMap<FixtureId, FixtureHandle>,
     joints: HashMap<JointId, JointHandle>,
 }
 
@@ -38,12 +69,14 @@ impl IdToHandle {
     pub fn new() -> Self {
         IdToHandle {
             bodies: HashMap::new(),
+            fixtures: HashMap::new(),
             joints: HashMap::new(),
         }
     }
 
     pub fn clear(&mut self) {
         self.bodies.clear();
+        self.fixtures.clear();
         self.joints.clear();
     }
 
@@ -52,8 +85,35 @@ impl IdToHandle {
     }
 
     pub fn insert_body(&mut self, id: BodyId, handle: BodyHandle) {
-        if self.bodies.insert(id, handle).is_some() {
-            panic!("body id duplicate");
+        self.try_insert_body(id, handle).expect("body id duplicate")
+    }
+
+    pub fn try_insert_body(&mut self, id: BodyId, handle: BodyHandle) -> Result<(), Error> {
+        match self.bodies.entry(id) {
+            Entry::Occupied(_) => Err(Error::SnapshotMismatch),
+            Entry::Vacant(e) => {
+                e.insert(handle);
+                Ok(())
+            }
+        }
+    }
+
+    /// The handle of a fixture of the body `body_handle(id.0)`.
+    pub fn fixture_handle(&self, id: FixtureId) -> Option<FixtureHandle> {
+        self.fixtures.get(&id).cloned()
+    }
+
+    pub fn insert_fixture(&mut self, id: FixtureId, handle: FixtureHandle) {
+        self.try_insert_fixture(id, handle).expect("fixture id duplicate")
+    }
+
+    pub fn try_insert_fixture(&mut self, id: FixtureId, handle: FixtureHandle) -> Result<(), Error> {
+        match self.fixtures.entry(id) {
+            Entry::Occupied(_) => Err(Error::SnapshotMismatch),
+            Entry::Vacant(e) => {
+                e.insert(handle);
+                Ok(())
+            }
         }
     }
 
@@ -62,8 +122,16 @@ impl IdToHandle {
     }
 
     pub fn insert_joint(&mut self, id: JointId, handle: JointHandle) {
-        if self.joints.insert(id, handle).is_some() {
-            panic!("joint id duplicate");
+        self.try_insert_joint(id, handle).expect("joint id duplicate")
+    }
+
+    pub fn try_insert_joint(&mut self, id: JointId, handle: JointHandle) -> Result<(), Error> {
+        match self.joints.entry(id) {
+            Entry::Occupied(_) => Err(Error::SnapshotMismatch),
+            Entry::Vacant(e) => {
+                e.insert(handle);
+                Ok(())
+            }
         }
     }
 }
@@ -78,6 +146,8 @@ pub struct WorldSnapshot<U: UserDataTypes>
     config: WorldConfigSnapshot,
     bodies: Vec<CompleteBodySnapshot<U>>,
     joints: Vec<CompleteJointSnapshot<U>>,
+    #[serde(default)]
+    handles: Option<HandlesSnapshot>,
 }
 
 type CompleteBodySnapshot<U: UserDataTypes> = (BodyId, BodySnapshot, U::BodyData, Vec<CompleteFixtureSnapshot<U>>);
@@ -94,9 +164,11 @@ impl<U: UserDataTypes> WorldSnapshot<U>
               U::FixtureData: Serialize + Clone,
               U::JointData: Serialize + Clone
     {
+        let mut handles = HandlesSnapshot::new(world);
         let body_snapshots: Vec<_> = world.bodies()
-            .map(|(_, body)| {
+            .map(|(handle, body)| {
                 let body: &MetaBody<U> = &body.borrow();
+                handles.push_body(handle, body);
                 let fixture_snapshots: Vec<_> = body.fixtures()
                     .map(|(_, fixture)| {
                         let fixture: &MetaFixture<U> = &fixture.borrow();
@@ -110,8 +182,9 @@ impl<U: UserDataTypes> WorldSnapshot<U>
             .collect();
 
         let joint_snapshots: Vec<_> = world.joints()
-            .map(|(_, joint)| {
+            .map(|(handle, joint)| {
                 let joint: &MetaJoint<U> = &joint.borrow();
+                handles.joints.push(handle);
                 let (id, s) = JointSnapshot::take(joint);
                 (id, s, joint.user_data().clone())
             })
@@ -121,44 +194,137 @@ impl<U: UserDataTypes> WorldSnapshot<U>
             config: WorldConfigSnapshot::take(world),
             bodies: body_snapshots,
             joints: joint_snapshots,
+            handles: Some(handles),
         }
     }
 
     pub fn rebuild(&self, id_to_handle: &mut IdToHandle) -> World<U>
-        where U::BodyData: DeserializeOwned + Clone,
-              U::FixtureData: DeserializeOwned + Clone,
-              U::JointData: DeserializeOwned + Clone,
+        where U::BodyData: DeserializeOwned + Clone,
+              U::FixtureData: DeserializeOwned + Clone,
+              U::JointData: DeserializeOwned + Clone,
+    {
+        self.rebuild_with(id_to_handle, None).expect("body, fixture or joint id duplicate")
+    }
+
+    /// Rebuilds the world giving out the same handles as the original world,
+    /// which can only fail for snapshots without handles or edited ones.
+    pub fn restore(&self) -> Result<World<U>, Error>
+        where U::BodyData: DeserializeOwned + Clone,
+              U::FixtureData: DeserializeOwned + Clone,
+              U::JointData: DeserializeOwned + Clone,
+    {
+        let handles = self.handles.as_ref().ok_or(Error::SnapshotMismatch)?;
+        self.rebuild_with(&mut IdToHandle::new(), Some(handles))
+    }
+
+    fn rebuild_with(&self,
+                    id_to_handle: &mut IdToHandle,
+                    handles: Option<&HandlesSnapshot>)
+                    -> Result<World<U>, Error>
+        where U::BodyData: DeserializeOwned + Clone,
+              U::FixtureData: DeserializeOwned + Clone,
+              U::JointData: DeserializeOwned + Clone,
     {
         id_to_handle.clear();
         let mut world = self.config.rebuild();
 
-        for &(id, ref snapshot, ref data, ref fixtures) in &self.bodies {
+        for (i, &(id, ref snapshot, ref data, ref fixtures)) in self.bodies.iter().enumerate() {
+            if let Some(handles) = handles {
+                world.reserve_next_body(handles.body(i)?)?;
+            }
             let handle = snapshot.rebuild(&mut world, data.clone());
-            id_to_handle.insert_body(id, handle);
+            id_to_handle.try_insert_body(id, handle)?;
 
             let mut body = world.body_mut(handle);
-            for &(ref snapshot, ref data) in fixtures {
-                snapshot.rebuild(&mut body, data.clone());
+            for (j, &(ref snapshot, ref data)) in fixtures.iter().enumerate() {
+                if let Some(handles) = handles {
+                    body.reserve_next_fixture(handles.fixture(i, j)?)?;
+                }
+                let fixture = snapshot.rebuild(&mut body, data.clone());
+                if let Some(id) = snapshot.id {
+                    id_to_handle.try_insert_fixture(id, fixture)?;
+                }
+            }
+            if let Some(handles) = handles {
+                body.set_fixture_handle_layout(handles.fixture_layout(i)?)?;
             }
 
             snapshot.may_restore_mass_data(&mut body);
         }
 
-        let joints = self.joints.iter();
+        let joints = self.joints.iter().enumerate();
         let mut gear_joint_snapshots = Vec::new();
-        for &(id, ref snapshot, ref data) in joints {
+        for (i, &(id, ref snapshot, ref data)) in joints {
+            if let Some(handles) = handles {
+                world.reserve_next_joint(handles.joint(i)?)?;
+            }
             match snapshot.rebuild(&mut world, data.clone(), id_to_handle) {
-                Ok(handle) => id_to_handle.insert_joint(id, handle),
-                Err(gjs) => gear_joint_snapshots.push((id, gjs, data)), 
+                Ok(handle) => id_to_handle.try_insert_joint(id, handle)?,
+                Err(gjs) => gear_joint_snapshots.push((i, id, gjs, data)), 
             }
         }
 
-        for (id, gjs, data) in gear_joint_snapshots {
+        for (i, id, gjs, data) in gear_joint_snapshots {
+            if let Some(handles) = handles {
+                world.reserve_next_joint(handles.joint(i)?)?;
+            }
             let handle = gjs.rebuild(&mut world, data.clone(), id_to_handle);
-            id_to_handle.insert_joint(id, handle);
+            id_to_handle.try_insert_joint(id, handle)?;
         }
 
-        world
+        if let Some(handles) = handles {
+            world.set_handle_layouts(&handles.body_layout, &handles.joint_layout)?;
+        }
+        Ok(world)
+    }
+}
+
+/// The handles of a world and of the bodies, fixtures and joints of a snapshot
+/// in the same order, for `restore` to give them out again.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HandlesSnapshot {
+    pub body_layout: HandleLayout,
+    pub joint_layout: HandleLayout,
+    pub bodies: Vec<BodyHandle>,
+    pub fixture_layouts: Vec<HandleLayout>,
+    pub fixtures: Vec<Vec<FixtureHandle>>,
+    pub joints: Vec<JointHandle>,
+}
+
+impl HandlesSnapshot {
+    pub fn new<U: UserDataTypes>(world: &World<U>) -> Self {
+        let (body_layout, joint_layout) = world.handle_layouts();
+        HandlesSnapshot {
+            body_layout: body_layout,
+            joint_layout: joint_layout,
+            bodies: Vec::new(),
+            fixture_layouts: Vec::new(),
+            fixtures: Vec::new(),
+            joints: Vec::new(),
+        }
+    }
+
+    /// Records the handles of the body and of its fixtures in handle order.
+    pub fn push_body<U: UserDataTypes>(&mut self, handle: BodyHandle, body: &MetaBody<U>) {
+        self.bodies.push(handle);
+        self.fixture_layouts.push(body.fixture_handle_layout());
+        self.fixtures.push(body.fixtures().map(|(h, _)| h).collect());
+    }
+
+    fn body(&self, i: usize) -> Result<BodyHandle, Error> {
+        self.bodies.get(i).cloned().ok_or(Error::SnapshotMismatch)
+    }
+
+    fn fixture(&self, i: usize, j: usize) -> Result<FixtureHandle, Error> {
+        self.fixtures.get(i).and_then(|f| f.get(j)).cloned().ok_or(Error::SnapshotMismatch)
+    }
+
+    fn fixture_layout(&self, i: usize) -> Result<&HandleLayout, Error> {
+        self.fixture_layouts.get(i).ok_or(Error::SnapshotMismatch)
+    }
+
+    fn joint(&self, i: usize) -> Result<JointHandle, Error> {
+        self.joints.get(i).cloned().ok_or(Error::SnapshotMismatch)
     }
 }
 
@@ -315,6 +481,7 @@ impl MassSnapshot {
 
 snapshot! {
     fixture => FixtureSnapshot {
+        pub id: Option<FixtureId> ["default::id" => None],
         pub shape: ShapeSnapshot,
         pub friction: f32 ["default::friction" => 0.2],
         pub restitution: f32 ["default::restitution" => 0.],
@@ -327,6 +494,7 @@ snapshot! {
 impl FixtureSnapshot {
     pub fn take<U: UserDataTypes>(fixture: &MetaFixture<U>) -> Self {
         FixtureSnapshot {
+            id: Some(FixtureId(BodyId(fixture.body().index()), fixture.handle().index())),
             shape: ShapeSnapshot::take(&fixture.shape()),
             friction: fixture.friction(),
             restitution: fixture.restitution(),
@@ -0,0 +1,201 @@
+//! Snapshots that also capture the state Box2D carries from one step to the
+//! next: contact manifolds with their impulses, joint impulses, sleep timers,
+//! the broad-phase tree and the order of every internal list.
+//! A world rebuilt from an `ExactWorldSnapshot` steps exactly like the original.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use serde::ser::Serialize;
+use serde::de::DeserializeOwned;
+
+use wrap::*;
+use user_data::{UserDataTypes, UserData, RawUserData};
+use dynamics::world::state::{creation_order, StepState, BodyState, JointState, ContactRecord,
+                             ProxyTreeState, TreeNodeState, ffi};
+use super::*;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ExactWorldSnapshot<U: UserDataTypes>
+    where U::BodyData: Debug + Serialize + DeserializeOwned,
+          U::FixtureData: Debug + Serialize + DeserializeOwned,
+          U::JointData: Debug + Serialize + DeserializeOwned,
+{
+    /// Bodies, fixtures and joints in creation order.
+    world: WorldSnapshot<U>,
+    state: StepState,
+    bodies: Vec<BodyState>,
+    joints: Vec<JointState>,
+    /// In creation order.
+    contacts: Vec<ContactSnapshot>,
+    broad_phase: BroadPhaseSnapshot,
+}
+
+impl<U: UserDataTypes> ExactWorldSnapshot<U>
+    where U::BodyData: Debug + Serialize + DeserializeOwned,
+          U::FixtureData: Debug + Serialize + DeserializeOwned,
+          U::JointData: Debug + Serialize + DeserializeOwned,
+{
+    pub fn take(world: &World<U>) -> Self
+        where U::BodyData: Clone,
+              U::FixtureData: Clone,
+              U::JointData: Clone
+    {
+        unsafe {
+            let world_ptr = world.ptr();
+            // fixtures are referred to by their index in creation order
+            let mut fixture_indices = HashMap::new();
+            let mut handles = HandlesSnapshot::new(world);
+
+            let mut bodies = Vec::new();
+            let mut body_states = Vec::new();
+            for body_ptr in creation_order(ffi::World_get_body_list_const(world_ptr),
+                                           ffi::Body_get_next_const) {
+                let body = world.body(body_ptr.handle());
+                handles.bodies.push(body.handle());
+                handles.fixture_layouts.push(body.fixture_handle_layout());
+                let mut fixtures = Vec::new();
+                let mut fixture_handles = Vec::new();
+                for fixture_ptr in creation_order(ffi::Body_get_fixture_list_const(body_ptr),
+                                                  ffi::Fixture_get_next_const) {
+                    let fixture = body.fixture(fixture_ptr.handle());
+                    let index = fixture_indices.len();
+                    fixture_indices.insert(fixture_ptr, index);
+                    fixture_handles.push(fixture.handle());
+                    fixtures.push((FixtureSnapshot::take(&fixture), fixture.user_data().clone()));
+                }
+                handles.fixtures.push(fixture_handles);
+
+                let (id, snapshot) = BodySnapshot::take(&body);
+                bodies.push((id, snapshot, body.user_data().clone(), fixtures));
+                body_states.push(BodyState::take(&body));
+            }
+
+            let mut joints = Vec::new();
+            let mut joint_states = Vec::new();
+            for joint_ptr in creation_order(ffi::World_get_joint_list_const(world_ptr),
+                                            ffi::Joint_get_next_const) {
+                let joint = world.joint(joint_ptr.handle());
+                handles.joints.push(joint.handle());
+                let (id, snapshot) = JointSnapshot::take(&joint);
+                joints.push((id, snapshot, joint.user_data().clone()));
+                joint_states.push(JointState::take(&joint));
+            }
+
+            let fixture_index = |fixture: *const ffi::Fixture| fixture_indices[&fixture];
+            let contacts = ContactRecord::take_all(world_ptr, &fixture_index);
+
+            ExactWorldSnapshot {
+                world: WorldSnapshot {
+                    config: WorldConfigSnapshot::take(world),
+                    bodies: bodies,
+                    joints: joints,
+                    handles: Some(handles),
+                },
+                state: StepState::take(world),
+                bodies: body_states,
+                joints: joint_states,
+                contacts: contacts,
+                broad_phase: ProxyTreeState::take(world_ptr, &fixture_index),
+            }
+        }
+    }
+
+    pub fn rebuild(&self, id_to_handle: &mut IdToHandle) -> World<U>
+        where U::BodyData: Clone,
+              U::FixtureData: Clone,
+              U::JointData: Clone
+    {
+        self.try_rebuild(id_to_handle).expect("invalid exact world snapshot")
+    }
+
+    /// Fails on duplicate ids and on contacts or broad-phase nodes that do not
+    /// match the fixtures, as a deserialized snapshot may have.
+    pub fn try_rebuild(&self, id_to_handle: &mut IdToHandle) -> Result<World<U>, Error>
+        where U::BodyData: Clone,
+              U::FixtureData: Clone,
+              U::JointData: Clone
+    {
+        self.rebuild_with(id_to_handle, None)
+    }
+
+    /// Like `WorldSnapshot::restore`, gives out the handles of the original world.
+    pub fn restore(&self) -> Result<World<U>, Error>
+        where U::BodyData: Clone,
+              U::FixtureData: Clone,
+              U::JointData: Clone
+    {
+        let handles = self.world.handles.as_ref().ok_or(Error::SnapshotMismatch)?;
+        self.rebuild_with(&mut IdToHandle::new(), Some(handles))
+    }
+
+    fn rebuild_with(&self,
+                    id_to_handle: &mut IdToHandle,
+                    handles: Option<&HandlesSnapshot>)
+                    -> Result<World<U>, Error>
+        where U::BodyData: Clone,
+              U::FixtureData: Clone,
+              U::JointData: Clone
+    {
+        id_to_handle.clear();
+        let mut world = self.world.config.rebuild();
+
+        let mut fixtures = Vec::new();
+        let bodies = self.world.bodies.iter().zip(&self.bodies).enumerate();
+        for (i, (&(id, ref snapshot, ref data, ref fixture_snapshots), state)) in bodies {
+            if let Some(handles) = handles {
+                world.reserve_next_body(handles.body(i)?)?;
+            }
+            let handle = snapshot.rebuild(&mut world, data.clone());
+            id_to_handle.try_insert_body(id, handle)?;
+
+            let mut body = world.body_mut(handle);
+            for (j, &(ref snapshot, ref data)) in fixture_snapshots.iter().enumerate() {
+                if let Some(handles) = handles {
+                    body.reserve_next_fixture(handles.fixture(i, j)?)?;
+                }
+                let fixture = snapshot.rebuild(&mut body, data.clone());
+                if let Some(id) = snapshot.id {
+                    id_to_handle.try_insert_fixture(id, fixture)?;
+                }
+                fixtures.push(unsafe { body.fixture(fixture).ptr() as *mut ffi::Fixture });
+            }
+            if let Some(handles) = handles {
+                body.set_fixture_handle_layout(handles.fixture_layout(i)?)?;
+            }
+            // overwrites the mass computed from the fixtures
+            state.restore(&mut body);
+        }
+
+        let joints = self.world.joints.iter().zip(&self.joints).enumerate();
+        for (i, (&(id, ref snapshot, ref data), state)) in joints {
+            if let Some(handles) = handles {
+                world.reserve_next_joint(handles.joint(i)?)?;
+            }
+            let handle = match snapshot.rebuild(&mut world, data.clone(), id_to_handle) {
+                Ok(handle) => handle,
+                // the joints of a gear joint are older than it, so already rebuilt
+                Err(gjs) => gjs.rebuild(&mut world, data.clone(), id_to_handle),
+            };
+            id_to_handle.try_insert_joint(id, handle)?;
+            state.restore(&mut world.joint_mut(handle));
+        }
+
+        let fixture_ptr = |&i: &usize| fixtures.get(i).cloned();
+        unsafe {
+            ContactRecord::restore_all(world.mut_ptr(), &self.contacts, &fixture_ptr)?;
+        }
+
+        if let Some(handles) = handles {
+            world.set_handle_layouts(&handles.body_layout, &handles.joint_layout)?;
+        }
+        unsafe {
+            self.broad_phase.restore(world.mut_ptr(), &fixture_ptr)?;
+        }
+        self.state.restore(&mut world);
+        Ok(world)
+    }
+}
+
+pub type ContactSnapshot = ContactRecord<usize>;
+pub type BroadPhaseSnapshot = ProxyTreeState<usize>;
+pub type TreeNodeSnapshot = TreeNodeState<usize>;
@@ -0,0 +1,1299 @@
+//! Scenes in the JSON format of the R.U.B.E. editor, known as b2json.
+//! `RubeWorld` only describes the layout of the document, which is read and
+//! written with a serde JSON implementation such as `serde_json`.
+//!
+//! Names, custom properties and images are given to the user data of bodies,
+//! fixtures and joints through `RubeData`, the images not attached to a body
+//! stay in `RubeWorld::images`.
+
+use std::collections::HashMap;
+use serde::ser::{Serialize, Serializer};
+use serde::de::{Deserialize, Deserializer, Error as DeError};
+
+use user_data::{UserDataTypes, UserData};
+use super::*;
+
+/// The name, custom properties and, for bodies, images of an item of a scene.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Metadata {
+    pub name: String,
+    pub custom_properties: Vec<CustomProperty>,
+    pub images: Vec<RubeImage>,
+}
+
+/// User data built from and turned back into the `Metadata` of a scene.
+pub trait RubeData {
+    fn from_metadata(metadata: Metadata) -> Self;
+    fn metadata(&self) -> Metadata;
+}
+
+impl RubeData for () {
+    fn from_metadata(_: Metadata) -> Self {}
+
+    fn metadata(&self) -> Metadata {
+        Metadata::default()
+    }
+}
+
+impl RubeData for Metadata {
+    fn from_metadata(metadata: Metadata) -> Self {
+        metadata
+    }
+
+    fn metadata(&self) -> Metadata {
+        self.clone()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomProperty {
+    pub name: String,
+    #[serde(flatten)]
+    pub value: PropertyValue,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PropertyValue {
+    Int(i32),
+    Float(#[serde(with = "float")] f32),
+    String(String),
+    Vec2(#[serde(with = "vec2")] [f32; 2]),
+    Bool(bool),
+    Color([u8; 4]),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RubeWorld {
+    #[serde(with = "vec2")]
+    pub gravity: [f32; 2],
+    pub allow_sleep: bool,
+    pub auto_clear_forces: bool,
+    pub warm_starting: bool,
+    pub continuous_physics: bool,
+    pub sub_stepping: bool,
+    pub velocity_iterations: i32,
+    pub position_iterations: i32,
+    #[serde(with = "float")]
+    pub steps_per_second: f32,
+    #[serde(rename = "body")]
+    pub bodies: Vec<RubeBody>,
+    #[serde(rename = "joint")]
+    pub joints: Vec<RubeJoint>,
+    #[serde(rename = "image")]
+    pub images: Vec<RubeImage>,
+    pub custom_properties: Vec<CustomProperty>,
+}
+
+impl Default for RubeWorld {
+    fn default() -> Self {
+        RubeWorld {
+            gravity: [0., 0.],
+            allow_sleep: true,
+            auto_clear_forces: true,
+            warm_starting: true,
+            continuous_physics: true,
+            sub_stepping: false,
+            velocity_iterations: 8,
+            position_iterations: 3,
+            steps_per_second: 60.,
+            bodies: Vec::new(),
+            joints: Vec::new(),
+            images: Vec::new(),
+            custom_properties: Vec::new(),
+        }
+    }
+}
+
+impl RubeWorld {
+    /// Describes the world, its bodies are numbered in handle order.
+    /// The world does not keep the images not attached to a body, the custom
+    /// properties of the scene and the stepping settings, use `update` on the
+    /// imported scene to keep them.
+    pub fn take<U: UserDataTypes>(world: &World<U>) -> Self
+        where U::BodyData: RubeData,
+              U::FixtureData: RubeData,
+              U::JointData: RubeData
+    {
+        let mut scene = RubeWorld::default();
+        scene.update(world);
+        scene
+    }
+
+    /// Replaces the settings, bodies, joints and body images with those of
+    /// the world, keeping everything the world does not know about.
+    pub fn update<U: UserDataTypes>(&mut self, world: &World<U>)
+        where U::BodyData: RubeData,
+              U::FixtureData: RubeData,
+              U::JointData: RubeData
+    {
+        let mut body_indices = HashMap::new();
+        let mut images = Vec::new();
+        let bodies = world.bodies()
+            .enumerate()
+            .map(|(i, (handle, body))| {
+                let body = body.borrow();
+                body_indices.insert(handle.index(), i as i32);
+                let metadata = body.user_data().metadata();
+                images.extend(metadata.images.into_iter().map(|image| RubeImage {
+                    body: i as i32,
+                    ..image
+                }));
+                RubeBody::take(&body, metadata.name, metadata.custom_properties)
+            })
+            .collect();
+
+        let mut joint_indices = HashMap::new();
+        for (i, (handle, _)) in world.joints().enumerate() {
+            joint_indices.insert(handle.index(), i as i32);
+        }
+        let joints = world.joints()
+            .map(|(_, joint)| RubeJoint::take(world, &joint.borrow(), &body_indices, &joint_indices))
+            .collect();
+
+        self.gravity = world.gravity().into();
+        self.allow_sleep = world.is_sleeping_allowed();
+        self.auto_clear_forces = world.is_auto_clearing_forces();
+        self.warm_starting = world.is_warm_starting();
+        self.continuous_physics = world.is_continuous_physics();
+        self.sub_stepping = world.is_sub_stepping();
+        self.bodies = bodies;
+        self.joints = joints;
+        self.images.retain(|image| image.body < 0);
+        self.images.extend(images);
+    }
+
+    pub fn rebuild<U: UserDataTypes>(&self, id_to_handle: &mut IdToHandle) -> World<U>
+        where U::BodyData: RubeData,
+              U::FixtureData: RubeData,
+              U::JointData: RubeData
+    {
+        self.try_rebuild(id_to_handle).expect("invalid RUBE scene")
+    }
+
+    /// Builds the world, the bodies, fixtures and joints get the ids
+    /// `BodyId(i)`, `FixtureId(BodyId(i), j)` and `JointId(k)` from their
+    /// position in the document.
+    pub fn try_rebuild<U: UserDataTypes>(&self,
+                                         id_to_handle: &mut IdToHandle)
+                                         -> Result<World<U>, Error>
+        where U::BodyData: RubeData,
+              U::FixtureData: RubeData,
+              U::JointData: RubeData
+    {
+        id_to_handle.clear();
+        let mut world = World::new(&self.gravity.into());
+        world.set_sleeping_allowed(self.allow_sleep);
+        world.set_auto_clearing_forces(self.auto_clear_forces);
+        world.set_warm_starting(self.warm_starting);
+        world.set_continuous_physics(self.continuous_physics);
+        world.set_sub_stepping(self.sub_stepping);
+
+        let mut body_images = vec![Vec::new(); self.bodies.len()];
+        for image in self.images.iter().filter(|image| image.body >= 0) {
+            body_images.get_mut(image.body as usize)
+                .ok_or(Error::SnapshotMismatch)?
+                .push(image.clone());
+        }
+
+        for (i, (body, images)) in self.bodies.iter().zip(body_images).enumerate() {
+            let data = U::BodyData::from_metadata(Metadata {
+                name: body.name.clone(),
+                custom_properties: body.custom_properties.clone(),
+                images: images,
+            });
+            let handle = world.try_create_body_with(&body.def(), data)?;
+            id_to_handle.try_insert_body(BodyId(i), handle)?;
+
+            let mut meta_body = world.body_mut(handle);
+            for (j, fixture) in body.fixtures.iter().enumerate() {
+                let fixture_handle = fixture.rebuild(&mut meta_body)?;
+                id_to_handle.try_insert_fixture(FixtureId(BodyId(i), j), fixture_handle)?;
+            }
+            if let Some(mass) = body.mass {
+                meta_body.set_mass_data(&MassData {
+                    mass: mass,
+                    center: body.mass_center.into(),
+                    inertia: body.mass_inertia,
+                });
+            }
+        }
+
+        // gear joints may come before the joints they join
+        let (gears, others): (Vec<_>, Vec<_>) = self.joints
+            .iter()
+            .enumerate()
+            .partition(|&(_, joint)| match joint.kind {
+                RubeJointKind::Gear(_) => true,
+                _ => false,
+            });
+        for (k, joint) in others.into_iter().chain(gears) {
+            let handle = joint.rebuild(&mut world, id_to_handle)?;
+            id_to_handle.try_insert_joint(JointId(k), handle)?;
+        }
+
+        Ok(world)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RubeBody {
+    pub name: String,
+    #[serde(rename = "type", with = "body_type")]
+    pub body_type: BodyType,
+    #[serde(with = "vec2")]
+    pub position: [f32; 2],
+    #[serde(with = "float")]
+    pub angle: f32,
+    #[serde(with = "vec2")]
+    pub linear_velocity: [f32; 2],
+    #[serde(with = "float")]
+    pub angular_velocity: f32,
+    #[serde(with = "float")]
+    pub linear_damping: f32,
+    #[serde(with = "float")]
+    pub angular_damping: f32,
+    #[serde(with = "float")]
+    pub gravity_scale: f32,
+    pub allow_sleep: bool,
+    pub awake: bool,
+    pub fixed_rotation: bool,
+    pub bullet: bool,
+    pub active: bool,
+    /// Replaces the mass computed from the fixtures.
+    #[serde(rename = "massData-mass",
+            with = "opt_float",
+            skip_serializing_if = "Option::is_none")]
+    pub mass: Option<f32>,
+    #[serde(rename = "massData-center", with = "vec2")]
+    pub mass_center: [f32; 2],
+    #[serde(rename = "massData-I", with = "float")]
+    pub mass_inertia: f32,
+    #[serde(rename = "fixture")]
+    pub fixtures: Vec<RubeFixture>,
+    pub custom_properties: Vec<CustomProperty>,
+}
+
+impl Default for RubeBody {
+    fn default() -> Self {
+        RubeBody {
+            name: String::new(),
+            body_type: BodyType::Static,
+            position: [0., 0.],
+            angle: 0.,
+            linear_velocity: [0., 0.],
+            angular_velocity: 0.,
+            linear_damping: 0.,
+            angular_damping: 0.,
+            gravity_scale: 1.,
+            allow_sleep: true,
+            awake: false,
+            fixed_rotation: false,
+            bullet: false,
+            active: true,
+            mass: None,
+            mass_center: [0., 0.],
+            mass_inertia: 0.,
+            fixtures: Vec::new(),
+            custom_properties: Vec::new(),
+        }
+    }
+}
+
+impl RubeBody {
+    fn take<U: UserDataTypes>(body: &MetaBody<U>,
+                              name: String,
+                              custom_properties: Vec<CustomProperty>)
+                              -> Self
+        where U::FixtureData: RubeData
+    {
+        let mass_data = body.mass_data();
+        RubeBody {
+            name: name,
+            body_type: body.body_type(),
+            position: (*body.position()).into(),
+            angle: body.angle(),
+            linear_velocity: (*body.linear_velocity()).into(),
+            angular_velocity: body.angular_velocity(),
+            linear_damping: body.linear_damping(),
+            angular_damping: body.angular_damping(),
+            gravity_scale: body.gravity_scale(),
+            allow_sleep: body.is_sleeping_allowed(),
+            awake: body.is_awake(),
+            fixed_rotation: body.is_rotation_fixed(),
+            bullet: body.is_bullet(),
+            active: body.is_active(),
+            mass: match body.body_type() {
+                BodyType::Dynamic => Some(mass_data.mass),
+                _ => None,
+            },
+            mass_center: mass_data.center.into(),
+            mass_inertia: mass_data.inertia,
+            fixtures: body.fixtures()
+                .map(|(_, fixture)| RubeFixture::take(&fixture.borrow()))
+                .collect(),
+            custom_properties: custom_properties,
+        }
+    }
+
+    fn def(&self) -> BodyDef {
+        BodyDef {
+            body_type: self.body_type,
+            position: self.position.into(),
+            angle: self.angle,
+            linear_velocity: self.linear_velocity.into(),
+            angular_velocity: self.angular_velocity,
+            linear_damping: self.linear_damping,
+            angular_damping: self.angular_damping,
+            allow_sleep: self.allow_sleep,
+            awake: self.awake,
+            fixed_rotation: self.fixed_rotation,
+            bullet: self.bullet,
+            active: self.active,
+            gravity_scale: self.gravity_scale,
+            ..BodyDef::new()
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RubeFixture {
+    pub name: String,
+    #[serde(with = "float")]
+    pub density: f32,
+    #[serde(with = "float")]
+    pub friction: f32,
+    #[serde(with = "float")]
+    pub restitution: f32,
+    pub sensor: bool,
+    #[serde(rename = "filter-categoryBits")]
+    pub category_bits: u16,
+    #[serde(rename = "filter-maskBits")]
+    pub mask_bits: u16,
+    #[serde(rename = "filter-groupIndex")]
+    pub group_index: i16,
+    #[serde(flatten)]
+    pub shape: RubeShape,
+    pub custom_properties: Vec<CustomProperty>,
+}
+
+impl Default for RubeFixture {
+    fn default() -> Self {
+        RubeFixture {
+            name: String::new(),
+            density: 0.,
+            friction: 0.,
+            restitution: 0.,
+            sensor: false,
+            category_bits: 0x0001,
+            mask_bits: 0xFFFF,
+            group_index: 0,
+            shape: RubeShape::Circle(RubeCircle::default()),
+            custom_properties: Vec::new(),
+        }
+    }
+}
+
+impl RubeFixture {
+    fn take<U: UserDataTypes>(fixture: &MetaFixture<U>) -> Self
+        where U::FixtureData: RubeData
+    {
+        let metadata = fixture.user_data().metadata();
+        let filter = fixture.filter_data();
+        RubeFixture {
+            name: metadata.name,
+            density: fixture.density(),
+            friction: fixture.friction(),
+            restitution: fixture.restitution(),
+            sensor: fixture.is_sensor(),
+            category_bits: filter.category_bits,
+            mask_bits: filter.mask_bits,
+            group_index: filter.group_index,
+            shape: RubeShape::take(&fixture.shape()),
+            custom_properties: metadata.custom_properties,
+        }
+    }
+
+    fn rebuild<U: UserDataTypes>(&self, body: &mut MetaBody<U>) -> Result<FixtureHandle, Error>
+        where U::FixtureData: RubeData
+    {
+        let shape = self.shape.rebuild()?;
+        let mut def = FixtureDef {
+            density: self.density,
+            friction: self.friction,
+            restitution: self.restitution,
+            is_sensor: self.sensor,
+            filter: Filter {
+                category_bits: self.category_bits,
+                mask_bits: self.mask_bits,
+                group_index: self.group_index,
+            },
+            ..FixtureDef::new()
+        };
+        let data = U::FixtureData::from_metadata(Metadata {
+            name: self.name.clone(),
+            custom_properties: self.custom_properties.clone(),
+            images: Vec::new(),
+        });
+        Ok(body.try_create_fixture_with(&shape, &mut def, data)?)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum RubeShape {
+    Circle(RubeCircle),
+    Edge(RubeEdge),
+    /// Polygons of two vertices are edges.
+    Polygon(RubePolygon),
+    /// Loops repeat their first vertex at the end.
+    Chain(RubeChain),
+}
+
+impl RubeShape {
+    fn take(shape: &UnknownShape) -> Self {
+        match shape {
+            &UnknownShape::Unknown => panic!("truly unknown shape"),
+            &UnknownShape::Circle(ref s) => {
+                RubeShape::Circle(RubeCircle {
+                    center: s.position().into(),
+                    radius: s.radius(),
+                })
+            }
+            &UnknownShape::Edge(ref s) => {
+                RubeShape::Edge(RubeEdge {
+                    vertex1: s.v1().into(),
+                    vertex2: s.v2().into(),
+                    has_vertex0: s.v0().is_some(),
+                    has_vertex3: s.v3().is_some(),
+                    vertex0: s.v0().map_or([0., 0.], Into::into),
+                    vertex3: s.v3().map_or([0., 0.], Into::into),
+                })
+            }
+            &UnknownShape::Polygon(ref s) => {
+                RubeShape::Polygon(RubePolygon {
+                    vertices: (0..s.vertex_count()).map(|i| *s.vertex(i)).collect(),
+                })
+            }
+            &UnknownShape::Chain(ref s) => {
+                RubeShape::Chain(RubeChain {
+                    vertices: Vec::from(s.vertices()),
+                    has_prev_vertex: s.prev_vertex().is_some(),
+                    has_next_vertex: s.next_vertex().is_some(),
+                    prev_vertex: s.prev_vertex().map_or([0., 0.], Into::into),
+                    next_vertex: s.next_vertex().map_or([0., 0.], Into::into),
+                })
+            }
+        }
+    }
+
+    fn rebuild(&self) -> Result<UnknownShape, Error> {
+        let edge = |v1: &Vec2, v2: &Vec2, v0, v3| {
+            let mut edge = EdgeShape::new_with(v1, v2);
+            edge.set_v0(v0);
+            edge.set_v3(v3);
+            UnknownShape::Edge(edge)
+        };
+        let shape = match self {
+            &RubeShape::Circle(ref s) => {
+                UnknownShape::Circle(CircleShape::try_new(s.center.into(), s.radius)?)
+            }
+            &RubeShape::Edge(ref s) => {
+                edge(&s.vertex1.into(),
+                     &s.vertex2.into(),
+                     if s.has_vertex0 { Some(s.vertex0.into()) } else { None },
+                     if s.has_vertex3 { Some(s.vertex3.into()) } else { None })
+            }
+            &RubeShape::Polygon(ref s) if s.vertices.len() == 2 => {
+                edge(&s.vertices[0], &s.vertices[1], None, None)
+            }
+            &RubeShape::Polygon(ref s) => {
+                UnknownShape::Polygon(PolygonShape::try_new_with(&s.vertices)?)
+            }
+            &RubeShape::Chain(ref s) => {
+                let n = s.vertices.len();
+                let is_loop = n > 3 && s.vertices[0] == s.vertices[n - 1] &&
+                              s.has_prev_vertex && s.has_next_vertex;
+                if is_loop {
+                    UnknownShape::Chain(ChainShape::try_new_loop(&s.vertices[..n - 1])?)
+                } else {
+                    let mut chain = ChainShape::try_new_chain(&s.vertices)?;
+                    if s.has_prev_vertex {
+                        chain.set_prev_vertex(Some(s.prev_vertex.into()));
+                    }
+                    if s.has_next_vertex {
+                        chain.set_next_vertex(Some(s.next_vertex.into()));
+                    }
+                    UnknownShape::Chain(chain)
+                }
+            }
+        };
+        Ok(shape)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct RubeCircle {
+    #[serde(with = "vec2")]
+    pub center: [f32; 2],
+    #[serde(with = "float")]
+    pub radius: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RubeEdge {
+    #[serde(with = "vec2")]
+    pub vertex1: [f32; 2],
+    #[serde(with = "vec2")]
+    pub vertex2: [f32; 2],
+    pub has_vertex0: bool,
+    pub has_vertex3: bool,
+    #[serde(with = "vec2")]
+    pub vertex0: [f32; 2],
+    #[serde(with = "vec2")]
+    pub vertex3: [f32; 2],
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct RubePolygon {
+    #[serde(with = "vertices")]
+    pub vertices: Vec<Vec2>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RubeChain {
+    #[serde(with = "vertices")]
+    pub vertices: Vec<Vec2>,
+    pub has_prev_vertex: bool,
+    pub has_next_vertex: bool,
+    #[serde(with = "vec2")]
+    pub prev_vertex: [f32; 2],
+    #[serde(with = "vec2")]
+    pub next_vertex: [f32; 2],
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RubeJoint {
+    #[serde(default)]
+    pub name: String,
+    /// Indices of the bodies in the document, only informative for gear joints.
+    #[serde(default)]
+    pub body_a: i32,
+    #[serde(default)]
+    pub body_b: i32,
+    #[serde(default)]
+    pub collide_connected: bool,
+    #[serde(flatten)]
+    pub kind: RubeJointKind,
+    #[serde(default)]
+    pub custom_properties: Vec<CustomProperty>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum RubeJointKind {
+    Revolute(RubeRevoluteJoint),
+    Prismatic(RubePrismaticJoint),
+    Distance(RubeDistanceJoint),
+    Pulley(RubePulleyJoint),
+    Mouse(RubeMouseJoint),
+    Gear(RubeGearJoint),
+    Wheel(RubeWheelJoint),
+    Weld(RubeWeldJoint),
+    Friction(RubeFrictionJoint),
+    Rope(RubeRopeJoint),
+    Motor(RubeMotorJoint),
+}
+
+impl RubeJoint {
+    fn take<U: UserDataTypes>(world: &World<U>,
+                              joint: &MetaJoint<U>,
+                              body_indices: &HashMap<usize, i32>,
+                              joint_indices: &HashMap<usize, i32>)
+                              -> Self
+        where U::JointData: RubeData
+    {
+        let local_anchor_a = || world.body(joint.body_a()).local_point(&joint.anchor_a()).into();
+        let local_anchor_b = || world.body(joint.body_b()).local_point(&joint.anchor_b()).into();
+
+        let kind = match joint as &UnknownJoint {
+            &UnknownJoint::Unknown => panic!("truly unknown joint"),
+            &UnknownJoint::Revolute(ref j) => {
+                RubeJointKind::Revolute(RubeRevoluteJoint {
+                    anchor_a: (*j.local_anchor_a()).into(),
+                    anchor_b: (*j.local_anchor_b()).into(),
+                    ref_angle: j.reference_angle(),
+                    enable_limit: j.is_limit_enabled(),
+                    lower_limit: j.lower_limit(),
+                    upper_limit: j.upper_limit(),
+                    enable_motor: j.is_motor_enabled(),
+                    motor_speed: j.motor_speed(),
+                    max_motor_torque: j.max_motor_torque(),
+                })
+            }
+            &UnknownJoint::Prismatic(ref j) => {
+                RubeJointKind::Prismatic(RubePrismaticJoint {
+                    anchor_a: (*j.local_anchor_a()).into(),
+                    anchor_b: (*j.local_anchor_b()).into(),
+                    local_axis_a: (*j.local_axis_a()).into(),
+                    ref_angle: j.reference_angle(),
+                    enable_limit: j.is_limit_enabled(),
+                    lower_limit: j.lower_limit(),
+                    upper_limit: j.upper_limit(),
+                    enable_motor: j.is_motor_enabled(),
+                    motor_speed: j.motor_speed(),
+                    max_motor_force: j.max_motor_force(),
+                })
+            }
+            &UnknownJoint::Distance(ref j) => {
+                RubeJointKind::Distance(RubeDistanceJoint {
+                    anchor_a: (*j.local_anchor_a()).into(),
+                    anchor_b: (*j.local_anchor_b()).into(),
+                    length: j.length(),
+                    frequency: j.frequency(),
+                    damping_ratio: j.damping_ratio(),
+                })
+            }
+            &UnknownJoint::Pulley(ref j) => {
+                RubeJointKind::Pulley(RubePulleyJoint {
+                    anchor_a: local_anchor_a(),
+                    anchor_b: local_anchor_b(),
+                    ground_anchor_a: j.ground_anchor_a().into(),
+                    ground_anchor_b: j.ground_anchor_b().into(),
+                    length_a: j.length_a(),
+                    length_b: j.length_b(),
+                    ratio: j.ratio(),
+                })
+            }
+            &UnknownJoint::Mouse(ref j) => {
+                RubeJointKind::Mouse(RubeMouseJoint {
+                    target: (*j.target()).into(),
+                    max_force: j.max_force(),
+                    frequency: j.frequency(),
+                    damping_ratio: j.damping_ratio(),
+                })
+            }
+            &UnknownJoint::Gear(ref j) => {
+                RubeJointKind::Gear(RubeGearJoint {
+                    joint1: joint_indices[&j.joint_1().index()],
+                    joint2: joint_indices[&j.joint_2().index()],
+                    ratio: j.ratio(),
+                })
+            }
+            &UnknownJoint::Wheel(ref j) => {
+                RubeJointKind::Wheel(RubeWheelJoint {
+                    anchor_a: (*j.local_anchor_a()).into(),
+                    anchor_b: (*j.local_anchor_b()).into(),
+                    local_axis_a: (*j.local_axis_a()).into(),
+                    enable_motor: j.is_motor_enabled(),
+                    motor_speed: j.motor_speed(),
+                    max_motor_torque: j.max_motor_torque(),
+                    spring_frequency: j.spring_frequency(),
+                    spring_damping_ratio: j.spring_damping_ratio(),
+                })
+            }
+            &UnknownJoint::Weld(ref j) => {
+                RubeJointKind::Weld(RubeWeldJoint {
+                    anchor_a: (*j.local_anchor_a()).into(),
+                    anchor_b: (*j.local_anchor_b()).into(),
+                    ref_angle: j.reference_angle(),
+                    frequency: j.frequency(),
+                    damping_ratio: j.damping_ratio(),
+                })
+            }
+            &UnknownJoint::Friction(ref j) => {
+                RubeJointKind::Friction(RubeFrictionJoint {
+                    anchor_a: (*j.local_anchor_a()).into(),
+                    anchor_b: (*j.local_anchor_b()).into(),
+                    max_force: j.max_force(),
+                    max_torque: j.max_torque(),
+                })
+            }
+            &UnknownJoint::Rope(ref j) => {
+                RubeJointKind::Rope(RubeRopeJoint {
+                    anchor_a: (*j.local_anchor_a()).into(),
+                    anchor_b: (*j.local_anchor_b()).into(),
+                    max_length: j.max_length(),
+                })
+            }
+            &UnknownJoint::Motor(ref j) => {
+                let linear_offset = (*j.linear_offset()).into();
+                RubeJointKind::Motor(RubeMotorJoint {
+                    anchor_a: linear_offset,
+                    linear_offset: Some(linear_offset),
+                    ref_angle: j.angular_offset(),
+                    max_force: j.max_force(),
+                    max_torque: j.max_torque(),
+                    correction_factor: j.correction_factor(),
+                })
+            }
+        };
+
+        let metadata = joint.user_data().metadata();
+        RubeJoint {
+            name: metadata.name,
+            body_a: body_indices[&joint.body_a().index()],
+            body_b: body_indices[&joint.body_b().index()],
+            collide_connected: joint.is_collide_connected(),
+            kind: kind,
+            custom_properties: metadata.custom_properties,
+        }
+    }
+
+    fn rebuild<U: UserDataTypes>(&self,
+                                 world: &mut World<U>,
+                                 id_to_handle: &IdToHandle)
+                                 -> Result<JointHandle, Error>
+        where U::JointData: RubeData
+    {
+        let body = |i: i32| id_to_handle.body_handle(BodyId(i as usize)).ok_or(Error::SnapshotMismatch);
+        let joint = |i: i32| id_to_handle.joint_handle(JointId(i as usize)).ok_or(Error::SnapshotMismatch);
+        let body_a = body(self.body_a);
+        let body_b = body(self.body_b);
+        let data = U::JointData::from_metadata(Metadata {
+            name: self.name.clone(),
+            custom_properties: self.custom_properties.clone(),
+            images: Vec::new(),
+        });
+
+        match self.kind {
+            RubeJointKind::Revolute(ref j) => {
+                world.try_create_joint_with(&RevoluteJointDef {
+                                                body_a: body_a?,
+                                                body_b: body_b?,
+                                                collide_connected: self.collide_connected,
+                                                local_anchor_a: j.anchor_a.into(),
+                                                local_anchor_b: j.anchor_b.into(),
+                                                reference_angle: j.ref_angle,
+                                                enable_limit: j.enable_limit,
+                                                lower_angle: j.lower_limit,
+                                                upper_angle: j.upper_limit,
+                                                enable_motor: j.enable_motor,
+                                                motor_speed: j.motor_speed,
+                                                max_motor_torque: j.max_motor_torque,
+                                            },
+                                            data)
+            }
+            RubeJointKind::Prismatic(ref j) => {
+                world.try_create_joint_with(&PrismaticJointDef {
+                                                body_a: body_a?,
+                                                body_b: body_b?,
+                                                collide_connected: self.collide_connected,
+                                                local_anchor_a: j.anchor_a.into(),
+                                                local_anchor_b: j.anchor_b.into(),
+                                                local_axis_a: j.local_axis_a.into(),
+                                                reference_angle: j.ref_angle,
+                                                enable_limit: j.enable_limit,
+                                                lower_translation: j.lower_limit,
+                                                upper_translation: j.upper_limit,
+                                                enable_motor: j.enable_motor,
+                                                max_motor_force: j.max_motor_force,
+                                                motor_speed: j.motor_speed,
+                                            },
+                                            data)
+            }
+            RubeJointKind::Distance(ref j) => {
+                world.try_create_joint_with(&DistanceJointDef {
+                                                body_a: body_a?,
+                                                body_b: body_b?,
+                                                collide_connected: self.collide_connected,
+                                                local_anchor_a: j.anchor_a.into(),
+                                                local_anchor_b: j.anchor_b.into(),
+                                                length: j.length,
+                                                frequency: j.frequency,
+                                                damping_ratio: j.damping_ratio,
+                                            },
+                                            data)
+            }
+            RubeJointKind::Pulley(ref j) => {
+                world.try_create_joint_with(&PulleyJointDef {
+                                                body_a: body_a?,
+                                                body_b: body_b?,
+                                                collide_connected: self.collide_connected,
+                                                ground_anchor_a: j.ground_anchor_a.into(),
+                                                ground_anchor_b: j.ground_anchor_b.into(),
+                                                local_anchor_a: j.anchor_a.into(),
+                                                local_anchor_b: j.anchor_b.into(),
+                                                length_a: j.length_a,
+                                                length_b: j.length_b,
+                                                ratio: j.ratio,
+                                            },
+                                            data)
+            }
+            RubeJointKind::Mouse(ref j) => {
+                let handle = world.try_create_joint_with(&MouseJointDef {
+                                                             body_a: body_a?,
+                                                             body_b: body_b?,
+                                                             collide_connected: self.collide_connected,
+                                                             target: j.target.into(),
+                                                             max_force: j.max_force,
+                                                             frequency: j.frequency,
+                                                             damping_ratio: j.damping_ratio,
+                                                         },
+                                                         data)?;
+                // we need to set the target after the joint creation
+                match &mut world.joint_mut(handle) as &mut UnknownJoint {
+                    &mut UnknownJoint::Mouse(ref mut joint) => joint.set_target(&j.target.into()),
+                    _ => unreachable!(),
+                }
+                Ok(handle)
+            }
+            RubeJointKind::Gear(ref j) => {
+                world.try_create_joint_with(&GearJointDef {
+                                                collide_connected: self.collide_connected,
+                                                joint_1: joint(j.joint1)?,
+                                                joint_2: joint(j.joint2)?,
+                                                ratio: j.ratio,
+                                            },
+                                            data)
+            }
+            RubeJointKind::Wheel(ref j) => {
+                world.try_create_joint_with(&WheelJointDef {
+                                                body_a: body_a?,
+                                                body_b: body_b?,
+                                                collide_connected: self.collide_connected,
+                                                local_anchor_a: j.anchor_a.into(),
+                                                local_anchor_b: j.anchor_b.into(),
+                                                local_axis_a: j.local_axis_a.into(),
+                                                enable_motor: j.enable_motor,
+                                                max_motor_torque: j.max_motor_torque,
+                                                motor_speed: j.motor_speed,
+                                                frequency: j.spring_frequency,
+                                                damping_ratio: j.spring_damping_ratio,
+                                            },
+                                            data)
+            }
+            RubeJointKind::Weld(ref j) => {
+                world.try_create_joint_with(&WeldJointDef {
+                                                body_a: body_a?,
+                                                body_b: body_b?,
+                                                collide_connected: self.collide_connected,
+                                                local_anchor_a: j.anchor_a.into(),
+                                                local_anchor_b: j.anchor_b.into(),
+                                                reference_angle: j.ref_angle,
+                                                frequency: j.frequency,
+                                                damping_ratio: j.damping_ratio,
+                                            },
+                                            data)
+            }
+            RubeJointKind::Friction(ref j) => {
+                world.try_create_joint_with(&FrictionJointDef {
+                                                body_a: body_a?,
+                                                body_b: body_b?,
+                                                collide_connected: self.collide_connected,
+                                                local_anchor_a: j.anchor_a.into(),
+                                                local_anchor_b: j.anchor_b.into(),
+                                                max_force: j.max_force,
+                                                max_torque: j.max_torque,
+                                            },
+                                            data)
+            }
+            RubeJointKind::Rope(ref j) => {
+                world.try_create_joint_with(&RopeJointDef {
+                                                body_a: body_a?,
+                                                body_b: body_b?,
+                                                collide_connected: self.collide_connected,
+                                                local_anchor_a: j.anchor_a.into(),
+                                                local_anchor_b: j.anchor_b.into(),
+                                                max_length: j.max_length,
+                                            },
+                                            data)
+            }
+            RubeJointKind::Motor(ref j) => {
+                world.try_create_joint_with(&MotorJointDef {
+                                                body_a: body_a?,
+                                                body_b: body_b?,
+                                                collide_connected: self.collide_connected,
+                                                linear_offset: j.linear_offset
+                                                    .unwrap_or(j.anchor_a)
+                                                    .into(),
+                                                angular_offset: j.ref_angle,
+                                                max_force: j.max_force,
+                                                max_torque: j.max_torque,
+                                                correction_factor: j.correction_factor,
+                                            },
+                                            data)
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RubeRevoluteJoint {
+    #[serde(with = "vec2")]
+    pub anchor_a: [f32; 2],
+    #[serde(with = "vec2")]
+    pub anchor_b: [f32; 2],
+    #[serde(with = "float")]
+    pub ref_angle: f32,
+    pub enable_limit: bool,
+    #[serde(with = "float")]
+    pub lower_limit: f32,
+    #[serde(with = "float")]
+    pub upper_limit: f32,
+    pub enable_motor: bool,
+    #[serde(with = "float")]
+    pub motor_speed: f32,
+    #[serde(with = "float")]
+    pub max_motor_torque: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RubePrismaticJoint {
+    #[serde(with = "vec2")]
+    pub anchor_a: [f32; 2],
+    #[serde(with = "vec2")]
+    pub anchor_b: [f32; 2],
+    #[serde(with = "vec2", alias = "localAxis1")]
+    pub local_axis_a: [f32; 2],
+    #[serde(with = "float")]
+    pub ref_angle: f32,
+    pub enable_limit: bool,
+    #[serde(with = "float")]
+    pub lower_limit: f32,
+    #[serde(with = "float")]
+    pub upper_limit: f32,
+    pub enable_motor: bool,
+    #[serde(with = "float")]
+    pub motor_speed: f32,
+    #[serde(with = "float")]
+    pub max_motor_force: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RubeDistanceJoint {
+    #[serde(with = "vec2")]
+    pub anchor_a: [f32; 2],
+    #[serde(with = "vec2")]
+    pub anchor_b: [f32; 2],
+    #[serde(with = "float")]
+    pub length: f32,
+    #[serde(with = "float")]
+    pub frequency: f32,
+    #[serde(with = "float")]
+    pub damping_ratio: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RubePulleyJoint {
+    #[serde(with = "vec2")]
+    pub anchor_a: [f32; 2],
+    #[serde(with = "vec2")]
+    pub anchor_b: [f32; 2],
+    #[serde(with = "vec2")]
+    pub ground_anchor_a: [f32; 2],
+    #[serde(with = "vec2")]
+    pub ground_anchor_b: [f32; 2],
+    #[serde(with = "float")]
+    pub length_a: f32,
+    #[serde(with = "float")]
+    pub length_b: f32,
+    #[serde(with = "float")]
+    pub ratio: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RubeMouseJoint {
+    #[serde(with = "vec2")]
+    pub target: [f32; 2],
+    #[serde(with = "float")]
+    pub max_force: f32,
+    #[serde(with = "float")]
+    pub frequency: f32,
+    #[serde(with = "float")]
+    pub damping_ratio: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct RubeGearJoint {
+    /// Indices of the joints in the document.
+    pub joint1: i32,
+    pub joint2: i32,
+    #[serde(with = "float")]
+    pub ratio: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RubeWheelJoint {
+    #[serde(with = "vec2")]
+    pub anchor_a: [f32; 2],
+    #[serde(with = "vec2")]
+    pub anchor_b: [f32; 2],
+    #[serde(with = "vec2")]
+    pub local_axis_a: [f32; 2],
+    pub enable_motor: bool,
+    #[serde(with = "float")]
+    pub motor_speed: f32,
+    #[serde(with = "float")]
+    pub max_motor_torque: f32,
+    #[serde(with = "float")]
+    pub spring_frequency: f32,
+    #[serde(with = "float")]
+    pub spring_damping_ratio: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RubeWeldJoint {
+    #[serde(with = "vec2")]
+    pub anchor_a: [f32; 2],
+    #[serde(with = "vec2")]
+    pub anchor_b: [f32; 2],
+    #[serde(with = "float")]
+    pub ref_angle: f32,
+    #[serde(with = "float")]
+    pub frequency: f32,
+    #[serde(with = "float")]
+    pub damping_ratio: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RubeFrictionJoint {
+    #[serde(with = "vec2")]
+    pub anchor_a: [f32; 2],
+    #[serde(with = "vec2")]
+    pub anchor_b: [f32; 2],
+    #[serde(with = "float")]
+    pub max_force: f32,
+    #[serde(with = "float")]
+    pub max_torque: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RubeRopeJoint {
+    #[serde(with = "vec2")]
+    pub anchor_a: [f32; 2],
+    #[serde(with = "vec2")]
+    pub anchor_b: [f32; 2],
+    #[serde(with = "float")]
+    pub max_length: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RubeMotorJoint {
+    /// The linear offset in older documents.
+    #[serde(with = "vec2")]
+    pub anchor_a: [f32; 2],
+    #[serde(with = "opt_vec2", skip_serializing_if = "Option::is_none")]
+    pub linear_offset: Option<[f32; 2]>,
+    /// The angular offset.
+    #[serde(with = "float")]
+    pub ref_angle: f32,
+    #[serde(with = "float")]
+    pub max_force: f32,
+    #[serde(with = "float")]
+    pub max_torque: f32,
+    #[serde(with = "float")]
+    pub correction_factor: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RubeImage {
+    pub name: String,
+    pub file: String,
+    /// Index of the body in the document, or -1.
+    pub body: i32,
+    #[serde(with = "vec2")]
+    pub center: [f32; 2],
+    #[serde(with = "float")]
+    pub angle: f32,
+    /// The height of the image.
+    #[serde(with = "float")]
+    pub scale: f32,
+    #[serde(with = "float")]
+    pub aspect_scale: f32,
+    #[serde(with = "float")]
+    pub opacity: f32,
+    pub flip: bool,
+    pub filter: i32,
+    pub render_order: i32,
+    pub color_tint: [u8; 4],
+    pub custom_properties: Vec<CustomProperty>,
+}
+
+impl Default for RubeImage {
+    fn default() -> Self {
+        RubeImage {
+            name: String::new(),
+            file: String::new(),
+            body: -1,
+            center: [0., 0.],
+            angle: 0.,
+            scale: 1.,
+            aspect_scale: 1.,
+            opacity: 1.,
+            flip: false,
+            filter: 1,
+            render_order: 0,
+            color_tint: [255; 4],
+            custom_properties: Vec::new(),
+        }
+    }
+}
+
+/// Floats are numbers, or strings of the hexadecimal bits as written by
+/// b2dJson in its hex float mode, e.g. `"3F800000"` for `1`.
+mod float {
+    use super::*;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+        Number(f32),
+        Hex(String),
+    }
+
+    /// A float read in either form.
+    #[derive(Serialize)]
+    pub struct Float(pub f32);
+
+    impl<'de> Deserialize<'de> for Float {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Float, D::Error> {
+            deserialize(deserializer).map(Float)
+        }
+    }
+
+    pub fn serialize<S: Serializer>(v: &f32, serializer: S) -> Result<S::Ok, S::Error> {
+        v.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f32, D::Error> {
+        match Repr::deserialize(deserializer)? {
+            Repr::Number(f) => Ok(f),
+            Repr::Hex(s) => {
+                u32::from_str_radix(&s, 16)
+                    .map(f32::from_bits)
+                    .map_err(|_| D::Error::custom(format!("{:?} is not a hex float", s)))
+            }
+        }
+    }
+}
+
+mod opt_float {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(v: &Option<f32>, serializer: S) -> Result<S::Ok, S::Error> {
+        v.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<f32>, D::Error>
+        where D: Deserializer<'de>
+    {
+        super::float::deserialize(deserializer).map(Some)
+    }
+}
+
+/// Vectors are `{ "x": .., "y": .. }`, or `0` when zero.
+mod vec2 {
+    use super::*;
+    use super::float::Float;
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+        Zero(Float),
+        Xy { x: Float, y: Float },
+    }
+
+    pub fn serialize<S: Serializer>(v: &[f32; 2], serializer: S) -> Result<S::Ok, S::Error> {
+        if *v == [0., 0.] {
+            // an integer, which b2dJson tells apart from objects
+            0.serialize(serializer)
+        } else {
+            Repr::Xy { x: Float(v[0]), y: Float(v[1]) }.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[f32; 2], D::Error> {
+        match Repr::deserialize(deserializer)? {
+            Repr::Zero(Float(z)) if z == 0. => Ok([0., 0.]),
+            Repr::Zero(Float(z)) => Err(D::Error::custom(format!("{} is not a vector", z))),
+            Repr::Xy { x, y } => Ok([x.0, y.0]),
+        }
+    }
+}
+
+mod opt_vec2 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(v: &Option<[f32; 2]>, serializer: S) -> Result<S::Ok, S::Error> {
+        match *v {
+            Some(ref v) => super::vec2::serialize(v, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<[f32; 2]>, D::Error> {
+        super::vec2::deserialize(deserializer).map(Some)
+    }
+}
+
+/// Vertex lists are `{ "x": [..], "y": [..] }`.
+mod vertices {
+    use super::*;
+    use super::float::Float;
+
+    #[derive(Serialize, Deserialize)]
+    struct Repr {
+        x: Vec<Float>,
+        y: Vec<Float>,
+    }
+
+    pub fn serialize<S: Serializer>(v: &Vec<Vec2>, serializer: S) -> Result<S::Ok, S::Error> {
+        Repr {
+            x: v.iter().map(|v| Float(v.x)).collect(),
+            y: v.iter().map(|v| Float(v.y)).collect(),
+        }.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Vec2>, D::Error> {
+        let repr = Repr::deserialize(deserializer)?;
+        if repr.x.len() != repr.y.len() {
+            return Err(D::Error::custom("as many x as y coordinates expected"));
+        }
+        Ok(repr.x.into_iter().zip(repr.y).map(|(x, y)| Vec2 { x: x.0, y: y.0 }).collect())
+    }
+}
+
+/// Body types are `0`, `1` and `2` for static, kinematic and dynamic.
+mod body_type {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(t: &BodyType, serializer: S) -> Result<S::Ok, S::Error> {
+        let i: u8 = match *t {
+            BodyType::Static => 0,
+            BodyType::Kinematic => 1,
+            BodyType::Dynamic => 2,
+        };
+        i.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BodyType, D::Error> {
+        match u8::deserialize(deserializer)? {
+            0 => Ok(BodyType::Static),
+            1 => Ok(BodyType::Kinematic),
+            2 => Ok(BodyType::Dynamic),
+            i => Err(D::Error::custom(format!("{} is not a body type", i))),
+        }
+    }
+}
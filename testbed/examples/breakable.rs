@@ -1,165 +1,158 @@
-extern crate wrapped2d;
-extern crate testbed;
-
-use std::rc::Rc;
-use std::cell::Cell;
-use wrapped2d::b2;
-use wrapped2d::user_data::NoUserData;
-use wrapped2d::dynamics::world::callbacks::ContactAccess;
-
-type UserData = NoUserData;
-type World = b2::World<NoUserData>;
-
-fn main() {
-    let (test, world) = Test::init();
-
-    let data = testbed::Data {
-        world: world,
-        camera: testbed::Camera {
-            position: [0., 10.],
-            size: [40., 40.]
-        },
-        draw_flags: b2::DrawFlags::DRAW_SHAPE |
-                    b2::DrawFlags::DRAW_AABB |
-                    b2::DrawFlags::DRAW_JOINT |
-                    b2::DrawFlags::DRAW_PAIR |
-                    b2::DrawFlags::DRAW_CENTER_OF_MASS
-    };
-
-    testbed::run(test, data, "Breakable", 400, 400);
-}
-
-fn create_ground(world: &mut World) -> b2::BodyHandle {
-    let bd = b2::BodyDef {
-        body_type: b2::BodyType::Static,
-        .. b2::BodyDef::new()
-    };
-
-    let ground = world.create_body(&bd);
-
-    let mut shape = b2::EdgeShape::new();
-    shape.set(&b2::Vec2 { x: -40., y: 0. }, &b2::Vec2 { x: 40., y: 0. });
-    world.body_mut(ground).create_fast_fixture(&shape, 0.);
-
-    ground
-}
-
-struct Test {
-    body: b2::BodyHandle,
-    piece2: b2::FixtureHandle,
-    shape2: b2::PolygonShape,
-    velocity: b2::Vec2,
-    angular_velocity: f32,
-    should_break: Rc<Cell<bool>>,
-    broke: bool
-}
-
-impl Test {
-    fn init() -> (Self, World) {
-        let mut world = World::new(&b2::Vec2 { x: 0., y: -10. });
-        create_ground(&mut world);
-        
-        let bd = b2::BodyDef {
-            body_type: b2::BodyType::Dynamic,
-            position: b2::Vec2 { x: 0., y: 40. },
-            angle: 0.25 * b2::PI,
-            .. b2::BodyDef::new()
-        };
-
-        let body = world.create_body(&bd);
-
-        let shape1 = b2::PolygonShape::new_oriented_box(0.5, 0.5, &b2::Vec2 { x: -0.5, y: 0. }, 0.);
-        let shape2 = b2::PolygonShape::new_oriented_box(0.5, 0.5, &b2::Vec2 { x: 0.5, y: 0. }, 0.);
-        let (_, piece2) = {
-            let mut body = world.body_mut(body);
-            (body.create_fast_fixture(&shape1, 1.), body.create_fast_fixture(&shape2, 1.))
-        };
-
-        let should_break = Rc::new(Cell::new(false));
-        world.set_contact_listener(Box::new(ContactListener { should_break: should_break.clone() }));
-
-        (Test {
-            body: body,
-            piece2: piece2, shape2: shape2,
-            velocity: b2::Vec2 { x: 0., y: 0. },
-            angular_velocity: 0.,
-            should_break: should_break,
-            broke: false
-        }, world)
-    }
-
-    fn break_it(&mut self, world: &mut World) {
-        let bd = {
-            let body = world.body(self.body);
-
-            b2::BodyDef {
-                body_type: b2::BodyType::Dynamic,
-                position: *body.position(),
-                angle: body.angle(),
-                .. b2::BodyDef::new()
-            }
-        };
-
-        let other = world.create_body(&bd);
-
-        let mut body = world.body_mut(self.body);
-        let center = *body.world_center();
-        body.destroy_fixture(self.piece2);
-
-        let mut other = world.body_mut(other);
-        self.piece2 = other.create_fast_fixture(&self.shape2, 1.);
-
-        // compute consistent velocities for new bodies based on cached velocities
-        let center1 = *body.world_center();
-        let center2 = *other.world_center();
-
-        let velocity1 = self.velocity + b2::cross_sv(self.angular_velocity, center1 - center);
-        let velocity2 = self.velocity + b2::cross_sv(self.angular_velocity, center2 - center);
-
-        body.set_angular_velocity(self.angular_velocity);
-        body.set_linear_velocity(&velocity1);
-
-        other.set_angular_velocity(self.angular_velocity);
-        other.set_linear_velocity(&velocity2);
-
-        self.broke = true;
-    }
-}
-
-impl testbed::Test<UserData> for Test {
-    fn step(&mut self, data: &mut testbed::Data<UserData>, dt: f32) {
-        if !self.broke {
-            if self.should_break.get() {
-                self.break_it(&mut data.world);
-            } else {
-                // cache velocities to improve movement on breakage
-                let body = data.world.body(self.body);
-                self.velocity = *body.linear_velocity();
-                self.angular_velocity = body.angular_velocity();
-            }
-        }
-
-        testbed::step(data, dt);
-    }
-}
-
-struct ContactListener {
-    should_break: Rc<Cell<bool>>
-}
-
-impl b2::ContactListener<UserData> for ContactListener {
-    fn post_solve(&mut self, ca: ContactAccess<UserData>, impulse: &b2::ContactImpulse) {
-        if !self.should_break.get() {
-            let count = ca.contact.manifold().count as usize;
-            
-            let mut max_impulse = 0f32;
-            for i in 0..count {
-                max_impulse = max_impulse.max(impulse.normal_impulses[i]);
-            }
-
-            if max_impulse > 40. {
-                self.should_break.set(true);
-            }
-        }
-    }
-}
+extern crate wrapped2d;
+extern crate testbed;
+
+use wrapped2d::b2;
+use wrapped2d::user_data::NoUserData;
+use wrapped2d::dynamics::world::callbacks::ContactAccess;
+
+type UserData = NoUserData;
+type World = b2::World<NoUserData>;
+
+fn main() {
+    let world = init();
+
+    let data = testbed::Data {
+        world: world,
+        camera: testbed::Camera {
+            position: [0., 10.],
+            size: [40., 40.]
+        },
+        draw_flags: b2::DrawFlags::DRAW_SHAPE |
+                    b2::DrawFlags::DRAW_AABB |
+                    b2::DrawFlags::DRAW_JOINT |
+                    b2::DrawFlags::DRAW_PAIR |
+                    b2::DrawFlags::DRAW_CENTER_OF_MASS
+    };
+
+    testbed::run((), data, "Breakable", 400, 400);
+}
+
+fn create_ground(world: &mut World) -> b2::BodyHandle {
+    let bd = b2::BodyDef {
+        body_type: b2::BodyType::Static,
+        .. b2::BodyDef::new()
+    };
+
+    let ground = world.create_body(&bd);
+
+    let mut shape = b2::EdgeShape::new();
+    shape.set(&b2::Vec2 { x: -40., y: 0. }, &b2::Vec2 { x: 40., y: 0. });
+    world.body_mut(ground).create_fast_fixture(&shape, 0.);
+
+    ground
+}
+
+fn init() -> World {
+    let mut world = World::new(&b2::Vec2 { x: 0., y: -10. });
+    create_ground(&mut world);
+    
+    let bd = b2::BodyDef {
+        body_type: b2::BodyType::Dynamic,
+        position: b2::Vec2 { x: 0., y: 40. },
+        angle: 0.25 * b2::PI,
+        .. b2::BodyDef::new()
+    };
+
+    let body = world.create_body(&bd);
+
+    let shape1 = b2::PolygonShape::new_oriented_box(0.5, 0.5, &b2::Vec2 { x: -0.5, y: 0. }, 0.);
+    let shape2 = b2::PolygonShape::new_oriented_box(0.5, 0.5, &b2::Vec2 { x: 0.5, y: 0. }, 0.);
+    let (_, piece2) = {
+        let mut body = world.body_mut(body);
+        (body.create_fast_fixture(&shape1, 1.), body.create_fast_fixture(&shape2, 1.))
+    };
+
+    world.set_contact_listener(Box::new(ContactListener {
+        body: body,
+        piece2: piece2,
+        shape2: Some(shape2),
+        velocity: b2::Vec2 { x: 0., y: 0. },
+        angular_velocity: 0.
+    }));
+
+    world
+}
+
+fn break_it(world: &mut World,
+            body: b2::BodyHandle,
+            piece2: b2::FixtureHandle,
+            shape2: &b2::PolygonShape,
+            velocity: b2::Vec2,
+            angular_velocity: f32) {
+    let bd = {
+        let body = world.body(body);
+
+        b2::BodyDef {
+            body_type: b2::BodyType::Dynamic,
+            position: *body.position(),
+            angle: body.angle(),
+            .. b2::BodyDef::new()
+        }
+    };
+
+    let other = world.create_body(&bd);
+
+    let mut body = world.body_mut(body);
+    let center = *body.world_center();
+    body.destroy_fixture(piece2);
+
+    let mut other = world.body_mut(other);
+    other.create_fast_fixture(shape2, 1.);
+
+    // compute consistent velocities for new bodies based on cached velocities
+    let center1 = *body.world_center();
+    let center2 = *other.world_center();
+
+    let velocity1 = velocity + b2::cross_sv(angular_velocity, center1 - center);
+    let velocity2 = velocity + b2::cross_sv(angular_velocity, center2 - center);
+
+    body.set_angular_velocity(angular_velocity);
+    body.set_linear_velocity(&velocity1);
+
+    other.set_angular_velocity(angular_velocity);
+    other.set_linear_velocity(&velocity2);
+}
+
+struct ContactListener {
+    body: b2::BodyHandle,
+    piece2: b2::FixtureHandle,
+    // taken when breaking
+    shape2: Option<b2::PolygonShape>,
+    velocity: b2::Vec2,
+    angular_velocity: f32
+}
+
+impl b2::ContactListener<UserData> for ContactListener {
+    fn pre_solve(&mut self, ca: ContactAccess<UserData>, _: &b2::Manifold) {
+        // cache velocities before the impact is solved to improve movement on breakage
+        for body in &[&ca.body_a, &ca.body_b] {
+            if body.handle() == self.body {
+                self.velocity = *body.linear_velocity();
+                self.angular_velocity = body.angular_velocity();
+            }
+        }
+    }
+
+    fn post_solve(&mut self, ca: ContactAccess<UserData>, impulse: &b2::ContactImpulse) {
+        if self.shape2.is_none() {
+            return;
+        }
+
+        let count = ca.contact.manifold().count as usize;
+
+        let mut max_impulse = 0f32;
+        for i in 0..count {
+            max_impulse = max_impulse.max(impulse.normal_impulses[i]);
+        }
+
+        if max_impulse > 40. {
+            // the world is locked, break the body once the step is over
+            let shape2 = self.shape2.take().unwrap();
+            let (body, piece2) = (self.body, self.piece2);
+            let (velocity, angular_velocity) = (self.velocity, self.angular_velocity);
+            ca.commands.push_fn(move |world| {
+                break_it(world, body, piece2, &shape2, velocity, angular_velocity)
+            });
+        }
+    }
+}
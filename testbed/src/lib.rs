@@ -166,28 +166,10 @@ where
     U::BodyData: Default,
     U::JointData: Default,
 {
-    let d = b2::Vec2 { x: 0.001, y: 0.001 };
-    let aabb = b2::AABB {
-        lower: p - d,
-        upper: p + d,
-    };
-
-    let mut result = None;
-    {
-        let mut callback = |body_h: b2::BodyHandle, fixture_h: b2::FixtureHandle| {
-            let body = world.body(body_h);
-            let fixture = body.fixture(fixture_h);
-
-            if body.body_type() != b2::BodyType::Static && fixture.test_point(&p) {
-                result = Some(body_h);
-                false
-            } else {
-                true
-            }
-        };
-        world.query_aabb(&mut callback, &aabb);
-    }
-    result
+    world
+        .query_point_iter(&p, None)
+        .map(|(body_h, _)| body_h)
+        .find(|&body_h| world.body(body_h).body_type() != b2::BodyType::Static)
 }
 
 fn ungrab<U>(world: &mut b2::World<U>, grabbing: &mut Option<b2::JointHandle>)
@@ -0,0 +1,157 @@
+extern crate wrapped2d;
+
+use std::rc::Rc;
+use std::cell::Cell;
+
+use wrapped2d::b2;
+use wrapped2d::dynamics::world::callbacks::{BodyAccess, FixtureAccess};
+use wrapped2d::user_data::NoUserData;
+
+struct Breaker;
+
+impl b2::ContactListener<NoUserData> for Breaker {
+    fn begin_contact(&mut self, ca: b2::ContactAccess<NoUserData>) {
+        for body in &[&ca.body_a, &ca.body_b] {
+            if body.body_type() == b2::BodyType::Dynamic {
+                ca.commands.destroy_body(body.handle());
+            }
+        }
+    }
+}
+
+#[test]
+fn destroy_from_listener() {
+    let mut world = b2::World::<NoUserData>::new(&b2::Vec2 { x: 0., y: -10. });
+    world.set_contact_listener(Box::new(Breaker));
+
+    let ground = world.create_body(&b2::BodyDef::new());
+    world.body_mut(ground).create_fast_fixture(&b2::PolygonShape::new_box(20., 1.), 0.);
+    let def = b2::BodyDef {
+        body_type: b2::BodyType::Dynamic,
+        position: b2::Vec2 { x: 0., y: 3. },
+        ..b2::BodyDef::new()
+    };
+    let fragile = world.create_body(&def);
+    world.body_mut(fragile).create_fast_fixture(&b2::PolygonShape::new_box(0.5, 0.5), 1.);
+
+    for _ in 0..120 {
+        world.step(1. / 60., 8, 3);
+    }
+    assert!(world.try_body(fragile).is_err());
+    assert_eq!(world.body_count(), 1);
+}
+
+#[test]
+fn create_then_chains_fixtures() {
+    let mut world = b2::World::<NoUserData>::new(&b2::Vec2 { x: 0., y: 0. });
+    let commands = world.commands();
+    let created = Rc::new(Cell::new(None));
+
+    let c = commands.clone();
+    let record = created.clone();
+    commands.create_body_then(b2::BodyDef::new(), (), move |_, body| {
+        let shape = b2::UnknownShape::Polygon(b2::PolygonShape::new_box(1., 1.));
+        c.create_fixture_then(body, shape, b2::FixtureDef::new(), (), move |_, handles| {
+            record.set(Some(handles));
+        });
+    });
+    world.apply_commands();
+
+    let (body, fixture) = created.get().unwrap();
+    assert!(world.body(body).try_fixture(fixture).is_ok());
+    assert!(commands.drain_errors().is_empty());
+}
+
+#[test]
+fn failed_commands_are_reported() {
+    let mut world = b2::World::<NoUserData>::new(&b2::Vec2 { x: 0., y: 0. });
+    let commands = world.commands();
+    let body = world.create_body(&b2::BodyDef::new());
+    world.destroy_body(body);
+
+    commands.destroy_body(body);
+    commands.apply_angular_impulse(body, 1., true);
+    world.apply_commands();
+
+    assert_eq!(commands.drain_errors(),
+               vec![wrapped2d::Error::InvalidHandle, wrapped2d::Error::InvalidHandle]);
+    assert!(commands.drain_errors().is_empty());
+}
+
+fn requeue(commands: b2::WorldCommands<NoUserData>, count: Rc<Cell<u32>>) {
+    let c = commands.clone();
+    commands.push_fn(move |_| {
+        count.set(count.get() + 1);
+        requeue(c, count);
+    });
+}
+
+#[test]
+fn requeuing_command_terminates() {
+    let mut world = b2::World::<NoUserData>::new(&b2::Vec2 { x: 0., y: 0. });
+    let commands = world.commands();
+    let count = Rc::new(Cell::new(0));
+    requeue(commands.clone(), count.clone());
+
+    world.apply_commands();
+    let first = count.get();
+    assert!(first > 0);
+    assert!(!commands.is_empty());
+
+    world.apply_commands();
+    assert!(count.get() > first);
+}
+
+struct Freezer;
+
+impl b2::ContactFilter<NoUserData> for Freezer {
+    fn should_collide(&mut self,
+                      body_a: BodyAccess<NoUserData>,
+                      _: FixtureAccess<NoUserData>,
+                      body_b: BodyAccess<NoUserData>,
+                      _: FixtureAccess<NoUserData>,
+                      commands: &b2::WorldCommands<NoUserData>)
+                      -> bool {
+        for body in &[&body_a, &body_b] {
+            if body.body_type() == b2::BodyType::Dynamic {
+                commands.set_body_type(body.handle(), b2::BodyType::Static);
+            }
+        }
+        false
+    }
+}
+
+#[test]
+fn commands_from_filter() {
+    let mut world = b2::World::<NoUserData>::new(&b2::Vec2 { x: 0., y: -10. });
+    world.set_contact_filter(Box::new(Freezer));
+
+    let ground = world.create_body(&b2::BodyDef::new());
+    world.body_mut(ground).create_fast_fixture(&b2::PolygonShape::new_box(20., 1.), 0.);
+    let def = b2::BodyDef {
+        body_type: b2::BodyType::Dynamic,
+        position: b2::Vec2 { x: 0., y: 2. },
+        ..b2::BodyDef::new()
+    };
+    let body = world.create_body(&def);
+    world.body_mut(body).create_fast_fixture(&b2::PolygonShape::new_box(0.5, 0.5), 1.);
+
+    for _ in 0..60 {
+        world.step(1. / 60., 8, 3);
+    }
+    assert_eq!(world.body(body).body_type(), b2::BodyType::Static);
+}
+
+#[test]
+fn dropping_the_world_discards_commands() {
+    let world = b2::World::<NoUserData>::new(&b2::Vec2 { x: 0., y: 0. });
+    let alive = Rc::new(());
+    let (tracker, commands) = (alive.clone(), world.commands());
+    world.commands().push_fn(move |_| {
+        let _ = (&tracker, &commands);
+    });
+    assert_eq!(Rc::strong_count(&alive), 2);
+
+    drop(world);
+    assert_eq!(Rc::strong_count(&alive), 1);
+}
@@ -0,0 +1,86 @@
+extern crate wrapped2d;
+
+use wrapped2d::b2;
+use wrapped2d::user_data::NoUserData;
+
+fn v(x: f32, y: f32) -> b2::Vec2 {
+    b2::Vec2 { x: x, y: y }
+}
+
+#[test]
+fn box_on_floor_events() {
+    let mut world = b2::World::<NoUserData>::new(&v(0., -10.));
+    let mut queue = b2::ContactEventQueue::new();
+    queue.record_pre_solve = true;
+    world.set_contact_listener(Box::new(queue));
+
+    let floor = world.create_body(&b2::BodyDef::new());
+    let floor_fixture = world.body_mut(floor)
+        .create_fast_fixture(&b2::PolygonShape::new_box(10., 1.), 0.);
+    let def = b2::BodyDef {
+        body_type: b2::BodyType::Dynamic,
+        position: v(0., 2.),
+        ..b2::BodyDef::new()
+    };
+    let body = world.create_body(&def);
+    let body_fixture = world.body_mut(body)
+        .create_fast_fixture(&b2::PolygonShape::new_box(0.5, 0.5), 1.);
+
+    let mut events = Vec::new();
+    for _ in 0..60 {
+        world.step(1. / 60., 8, 3);
+        events.extend(world.drain_contact_events());
+    }
+    // lifted away from the floor, the contact ends
+    {
+        let mut body = world.body_mut(body);
+        body.set_transform(&v(0., 10.), 0.);
+        // the box is asleep by now, Box2D does not update its contacts otherwise
+        body.set_awake(true);
+    }
+    world.step(1. / 60., 8, 3);
+    events.extend(world.drain_contact_events());
+    assert!(world.drain_contact_events().is_empty());
+
+    let mut kinds = Vec::new();
+    for event in &events {
+        let mut pair = [event.fixture_a, event.fixture_b];
+        pair.sort_by_key(|&(b, _)| b.index());
+        assert_eq!(pair, [(floor, floor_fixture), (body, body_fixture)]);
+
+        kinds.push(match event.kind {
+            b2::ContactEventKind::Begin(ref points) => {
+                assert!(!points.points.is_empty());
+                assert!((points.normal.y.abs() - 1.).abs() < 1e-3);
+                "begin"
+            }
+            b2::ContactEventKind::PreSolve(ref points) => {
+                assert!(!points.points.is_empty());
+                "pre"
+            }
+            b2::ContactEventKind::PostSolve(ref impulse) => {
+                assert!(impulse.count > 0);
+                assert!(impulse.normal_impulses[0] >= 0.);
+                "post"
+            }
+            b2::ContactEventKind::End => "end",
+        });
+    }
+
+    assert_eq!(kinds.first(), Some(&"begin"));
+    assert_eq!(kinds.last(), Some(&"end"));
+    assert_eq!(kinds.iter().filter(|&&k| k == "begin").count(), 1);
+    assert_eq!(kinds.iter().filter(|&&k| k == "end").count(), 1);
+    // every solve is announced by a pre-solve, right after the contact begins
+    assert_eq!(kinds[1], "pre");
+    for (i, &kind) in kinds.iter().enumerate() {
+        if kind == "post" {
+            assert_eq!(kinds[i - 1], "pre");
+        }
+    }
+    let impulses = events.iter().filter_map(|event| match event.kind {
+        b2::ContactEventKind::PostSolve(ref impulse) => Some(impulse.normal_impulses[0]),
+        _ => None,
+    });
+    assert!(impulses.fold(0., f32::max) > 0.);
+}
@@ -0,0 +1,63 @@
+extern crate wrapped2d;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use wrapped2d::b2;
+use wrapped2d::user_data::{UserData, UserDataTypes};
+
+struct Names;
+
+impl UserDataTypes for Names {
+    type BodyData = ();
+    type JointData = &'static str;
+    type FixtureData = &'static str;
+}
+
+#[derive(Clone, Default)]
+struct Goodbyes {
+    joints: Rc<RefCell<Vec<(b2::JointHandle, &'static str)>>>,
+    fixtures: Rc<RefCell<Vec<(b2::BodyHandle, b2::FixtureHandle, &'static str)>>>,
+}
+
+impl b2::DestructionListener<Names> for Goodbyes {
+    fn say_goodbye_to_joint(&mut self, joint: b2::JointHandle, data: &'static str) {
+        self.joints.borrow_mut().push((joint, data));
+    }
+
+    fn say_goodbye_to_fixture(&mut self,
+                              body: b2::BodyHandle,
+                              fixture: b2::FixtureHandle,
+                              data: &'static str) {
+        self.fixtures.borrow_mut().push((body, fixture, data));
+    }
+}
+
+#[test]
+fn implicit_destruction() {
+    let mut world = b2::World::<Names>::new(&b2::Vec2 { x: 0., y: -10. });
+    let goodbyes = Goodbyes::default();
+    world.set_destruction_listener(Box::new(goodbyes.clone()));
+
+    let ground = world.create_body(&b2::BodyDef::new());
+    let body = world.create_body(&b2::BodyDef {
+        body_type: b2::BodyType::Dynamic,
+        ..b2::BodyDef::new()
+    });
+    let shape = b2::PolygonShape::new_box(1., 1.);
+    let fixture = world.body_mut(body)
+        .create_fixture_with(&shape, &mut b2::FixtureDef::new(), "box");
+    let kept = world.body_mut(ground)
+        .create_fixture_with(&shape, &mut b2::FixtureDef::new(), "ground");
+    let joint = world.create_joint_with(&b2::RevoluteJointDef::new(ground, body), "hinge");
+
+    // explicit destruction does not call the listener
+    let other = world.create_joint_with(&b2::DistanceJointDef::new(ground, body), "rod");
+    world.destroy_joint(other);
+    assert!(goodbyes.joints.borrow().is_empty());
+
+    world.destroy_body(body);
+    assert_eq!(*goodbyes.joints.borrow(), vec![(joint, "hinge")]);
+    assert_eq!(*goodbyes.fixtures.borrow(), vec![(body, fixture, "box")]);
+    assert_eq!(world.joint_count(), 0);
+    assert_eq!(*world.body(ground).fixture(kept).user_data(), "ground");
+}
@@ -0,0 +1,167 @@
+#![cfg(feature = "serialize")]
+
+extern crate wrapped2d;
+extern crate serde_json;
+#[macro_use]
+extern crate serde_derive;
+
+use wrapped2d::b2;
+use wrapped2d::serialize::{BodyId, ExactWorldSnapshot, FixtureId, IdToHandle, WorldSnapshot};
+use wrapped2d::user_data::{NoUserData, UserDataTypes};
+use wrapped2d::Error;
+
+fn step(world: &mut b2::World<NoUserData>) {
+    world.step(1. / 60., 8, 3);
+}
+
+#[test]
+fn exact_snapshot() {
+    let mut world = b2::World::<NoUserData>::new(&b2::Vec2 { x: 0., y: -10. });
+
+    let ground = world.create_body(&b2::BodyDef::new());
+    world.body_mut(ground).create_fast_fixture(&b2::PolygonShape::new_box(20., 1.), 0.);
+
+    let mut def = b2::BodyDef::new();
+    def.body_type = b2::BodyType::Dynamic;
+    let mut bodies = Vec::new();
+    for i in 0..8 {
+        def.position = b2::Vec2 { x: 0.1 * i as f32, y: 2. + 2.1 * i as f32 };
+        let body = world.create_body(&def);
+        world.body_mut(body).create_fast_fixture(&b2::PolygonShape::new_box(0.5, 0.5), 1.);
+        bodies.push(body);
+    }
+
+    let pendulum = world.create_body(&b2::BodyDef {
+        position: b2::Vec2 { x: 5., y: 6. },
+        ..def.clone()
+    });
+    let circle = b2::CircleShape::new_with(b2::Vec2 { x: 0., y: 0. }, 0.5);
+    world.body_mut(pendulum).create_fast_fixture(&circle, 1.);
+    let joint_def = b2::RevoluteJointDef::new(ground, pendulum);
+    world.create_joint(&joint_def);
+    bodies.push(pendulum);
+
+    for _ in 0..40 {
+        step(&mut world);
+    }
+
+    let snapshot = ExactWorldSnapshot::take(&world);
+    let mut id_to_handle = IdToHandle::new();
+    let mut copy = snapshot.rebuild(&mut id_to_handle);
+
+    for _ in 0..120 {
+        step(&mut world);
+        step(&mut copy);
+    }
+
+    for &body in &bodies {
+        let copied = id_to_handle.body_handle(BodyId(body.index())).unwrap();
+        assert_eq!(world.body(body).transform(), copy.body(copied).transform());
+        assert_eq!(world.body(body).is_awake(), copy.body(copied).is_awake());
+    }
+}
+
+#[test]
+fn restore_keeps_handles() {
+    let mut world = b2::World::<NoUserData>::new(&b2::Vec2 { x: 0., y: -10. });
+    let mut def = b2::BodyDef::new();
+    def.body_type = b2::BodyType::Dynamic;
+    let a = world.create_body(&def);
+    let b = world.create_body(&def);
+    world.destroy_body(a);
+    let c = world.create_body(&def);
+    let shape = b2::CircleShape::new_with(b2::Vec2 { x: 0., y: 0. }, 1.);
+    let first = world.body_mut(c).create_fast_fixture(&shape, 1.);
+    let second = world.body_mut(c).create_fast_fixture(&shape, 1.);
+    world.body_mut(c).destroy_fixture(first);
+    world.create_joint(&b2::DistanceJointDef::new(b, c));
+
+    let mut copy = WorldSnapshot::take(&world).restore().unwrap();
+    assert!(copy.try_body(b).is_ok());
+    assert!(copy.body(c).try_fixture(second).is_ok());
+    assert_eq!(copy.joints().map(|(h, _)| h).collect::<Vec<_>>(),
+               world.joints().map(|(h, _)| h).collect::<Vec<_>>());
+
+    // both worlds give out the same handles from now on
+    assert_eq!(copy.create_body(&def), world.create_body(&def));
+    assert_eq!(copy.body_mut(c).create_fast_fixture(&shape, 1.),
+               world.body_mut(c).create_fast_fixture(&shape, 1.));
+}
+
+#[test]
+fn fixture_ids() {
+    let mut world = b2::World::<NoUserData>::new(&b2::Vec2 { x: 0., y: -10. });
+    let body = world.create_body(&b2::BodyDef::new());
+    let shape = b2::CircleShape::new_with(b2::Vec2 { x: 0., y: 0. }, 1.);
+    let first = world.body_mut(body).create_fast_fixture(&shape, 1.);
+    let second = world.body_mut(body).create_fast_fixture(&shape, 2.);
+    world.body_mut(body).destroy_fixture(first);
+
+    let mut id_to_handle = IdToHandle::new();
+    let copy = WorldSnapshot::take(&world).rebuild(&mut id_to_handle);
+    let copied_body = id_to_handle.body_handle(BodyId(body.index())).unwrap();
+    let copied = id_to_handle.fixture_handle(FixtureId(BodyId(body.index()), second.index()))
+        .unwrap();
+    assert_eq!(copy.body(copied_body).fixture(copied).density(), 2.);
+    assert!(id_to_handle.fixture_handle(FixtureId(BodyId(body.index()), first.index())).is_none());
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Data;
+
+impl UserDataTypes for Data {
+    type BodyData = ();
+    type JointData = ();
+    type FixtureData = ();
+}
+
+#[test]
+fn rebuild_checks_snapshot() {
+    let mut world = b2::World::<Data>::new(&b2::Vec2 { x: 0., y: -10. });
+    let ground = world.create_body(&b2::BodyDef::new());
+    let floor = b2::EdgeShape::new_with(&b2::Vec2 { x: -20., y: 1. }, &b2::Vec2 { x: 20., y: 1. });
+    let wall = b2::EdgeShape::new_with(&b2::Vec2 { x: 20., y: 1. }, &b2::Vec2 { x: 20., y: 9. });
+    world.body_mut(ground).create_fast_fixture(&floor, 0.);
+    world.body_mut(ground).create_fast_fixture(&wall, 0.);
+    let mut def = b2::BodyDef::new();
+    def.body_type = b2::BodyType::Dynamic;
+    def.position = b2::Vec2 { x: 0., y: 1.4 };
+    let body = world.create_body(&def);
+    world.body_mut(body).create_fast_fixture(&b2::PolygonShape::new_box(0.5, 0.5), 1.);
+    world.step(1. / 60., 8, 3);
+
+    let snapshot = ExactWorldSnapshot::take(&world);
+    let value = serde_json::to_value(&snapshot).unwrap();
+    assert!(!value["contacts"].as_array().unwrap().is_empty());
+
+    let tampered = |path: &[&str], replacement: serde_json::Value| {
+        let mut value = value.clone();
+        {
+            let mut field = &mut value;
+            for key in path {
+                field = match key.parse::<usize>() {
+                    Ok(i) => &mut field[i],
+                    Err(_) => &mut field[*key],
+                };
+            }
+            *field = replacement;
+        }
+        let snapshot: ExactWorldSnapshot<Data> = serde_json::from_value(value).unwrap();
+        snapshot.try_rebuild(&mut IdToHandle::new()).err()
+    };
+
+    assert!(snapshot.try_rebuild(&mut IdToHandle::new()).is_ok());
+    assert_eq!(tampered(&["contacts", "0", "fixture_a"], 9.into()),
+               Some(Error::SnapshotMismatch));
+    assert_eq!(tampered(&["contacts", "0", "child_b"], 1.into()),
+               Some(Error::SnapshotMismatch));
+    // Box2D has no contact between two edges
+    assert_eq!(tampered(&["contacts", "0", "fixture_b"], 1.into()),
+               Some(Error::SnapshotMismatch));
+    assert_eq!(tampered(&["broad_phase", "root"], 1000.into()),
+               Some(Error::SnapshotMismatch));
+    assert_eq!(tampered(&["broad_phase", "nodes", "0", "child2"], (-2).into()),
+               Some(Error::SnapshotMismatch));
+    assert_eq!(tampered(&["broad_phase", "moves"], vec![1000].into()),
+               Some(Error::SnapshotMismatch));
+}
@@ -0,0 +1,82 @@
+extern crate wrapped2d;
+
+use wrapped2d::b2;
+use wrapped2d::Error;
+use wrapped2d::user_data::NoUserData;
+
+fn world_with_bodies() -> (b2::World<NoUserData>, b2::BodyHandle, b2::BodyHandle) {
+    let mut world = b2::World::<NoUserData>::new(&b2::Vec2 { x: 0., y: -10. });
+    let ground = world.create_body(&b2::BodyDef::new());
+    let def = b2::BodyDef {
+        body_type: b2::BodyType::Dynamic,
+        position: b2::Vec2 { x: 0., y: 2. },
+        ..b2::BodyDef::new()
+    };
+    let body = world.create_body(&def);
+    (world, ground, body)
+}
+
+#[test]
+fn invalid_bodies() {
+    let (mut world, ground, body) = world_with_bodies();
+
+    let def = b2::DistanceJointDef::new(ground, ground);
+    assert_eq!(world.try_create_joint(&def).err(),
+               Some(Error::InvalidJointDef(b2::JointDefError::SameBody)));
+
+    let dead = world.create_body(&b2::BodyDef::new());
+    world.destroy_body(dead);
+    let def = b2::DistanceJointDef::new(ground, dead);
+    assert_eq!(world.try_create_joint(&def).err(),
+               Some(Error::InvalidJointDef(b2::JointDefError::InvalidBody)));
+
+    let def = b2::DistanceJointDef::new(ground, body);
+    assert!(world.try_create_joint(&def).is_ok());
+    assert_eq!(world.joint_count(), 1);
+}
+
+#[test]
+fn out_of_range() {
+    let (mut world, ground, body) = world_with_bodies();
+
+    let mut def = b2::DistanceJointDef::new(ground, body);
+    def.length = -1.;
+    assert_eq!(world.try_create_joint(&def).err(),
+               Some(Error::InvalidJointDef(b2::JointDefError::OutOfRange("length"))));
+    assert_eq!(world.joint_count(), 0);
+}
+
+#[test]
+fn gear_joints() {
+    let (mut world, ground, body) = world_with_bodies();
+    let revolute = world.create_joint(&b2::RevoluteJointDef::new(ground, body));
+    let distance = world.create_joint(&b2::DistanceJointDef::new(ground, body));
+
+    let def = b2::GearJointDef::new(revolute, distance);
+    assert_eq!(world.try_create_joint(&def).err(),
+               Some(Error::InvalidJointDef(
+                   b2::JointDefError::UnsupportedGearJoint(b2::JointType::Distance))));
+
+    world.destroy_joint(distance);
+    let def = b2::GearJointDef::new(revolute, distance);
+    assert_eq!(world.try_create_joint(&def).err(),
+               Some(Error::InvalidJointDef(b2::JointDefError::InvalidJoint)));
+    assert_eq!(world.joint_count(), 1);
+}
+
+#[test]
+#[should_panic]
+fn create_invalid_joint() {
+    let (mut world, ground, _) = world_with_bodies();
+    world.create_joint(&b2::DistanceJointDef::new(ground, ground));
+}
+
+#[test]
+fn borrowed_body() {
+    use b2::JointDef;
+
+    let (world, ground, body) = world_with_bodies();
+    let def = b2::DistanceJointDef::new(ground, body);
+    let _body = world.body_mut(body);
+    assert_eq!(def.validate(&world), Err(b2::JointDefError::AlreadyBorrowed));
+}
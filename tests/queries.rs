@@ -0,0 +1,112 @@
+extern crate wrapped2d;
+
+use wrapped2d::b2;
+use wrapped2d::user_data::NoUserData;
+
+fn v(x: f32, y: f32) -> b2::Vec2 {
+    b2::Vec2 { x: x, y: y }
+}
+
+fn world_with_floor() -> (b2::World<NoUserData>, b2::BodyHandle) {
+    let mut world = b2::World::<NoUserData>::new(&v(0., -10.));
+    let floor = world.create_body(&b2::BodyDef::new());
+    let chain = b2::ChainShape::new_chain(&[v(-3., 0.), v(-1., 0.), v(1., 0.), v(3., 0.)]);
+    world.body_mut(floor).create_fast_fixture(&chain, 0.);
+    (world, floor)
+}
+
+#[test]
+fn ray_cast_child_index() {
+    let (world, floor) = world_with_floor();
+
+    let hit = world.ray_cast_closest(&v(2., 1.), &v(2., -1.), None).unwrap();
+    assert_eq!(hit.body, floor);
+    assert_eq!(hit.child_index, 2);
+    assert_eq!(hit.point, v(2., 0.));
+
+    let hit = world.ray_cast_closest(&v(-2., 1.), &v(-2., -1.), None).unwrap();
+    assert_eq!(hit.child_index, 0);
+
+    // both children sharing the vertex are hit with the same fraction and normal
+    let hits = world.ray_cast_all(&v(-1., 1.), &v(-1., -1.), None);
+    let mut children: Vec<_> = hits.iter().map(|hit| hit.child_index).collect();
+    children.sort();
+    assert_eq!(children, vec![0, 1]);
+    assert!(world.ray_cast_closest(&v(4., 1.), &v(4., -1.), None).is_none());
+}
+
+#[test]
+fn queries_share_the_filter() {
+    let (mut world, floor) = world_with_floor();
+    let trigger = world.create_body(&b2::BodyDef::new());
+    let mut def = b2::FixtureDef { is_sensor: true, ..b2::FixtureDef::new() };
+    world.body_mut(trigger).create_fixture(&b2::CircleShape::new_with(v(0., 2.), 1.), &mut def);
+
+    let filter = b2::QueryFilter { ignore_sensors: true, ..b2::QueryFilter::new() };
+    assert_eq!(world.query_point_iter(&v(0., 2.), None).count(), 1);
+    assert_eq!(world.query_point_iter(&v(0., 2.), Some(&filter)).count(), 0);
+
+    let probe = b2::CircleShape::new_with(v(0., 0.), 0.5);
+    let xf = b2::Transform { pos: v(0., 2.), rot: b2::Rot::from_angle(0.) };
+    let mut overlaps = 0;
+    world.query_shape(&probe, &xf, Some(&filter), &mut |_, _, _| {
+        overlaps += 1;
+        true
+    });
+    assert_eq!(overlaps, 0);
+
+    // the sensor is skipped, the sweep reaches the floor
+    let mut hits = Vec::new();
+    world.shape_cast(&probe,
+                     &xf,
+                     v(0., -4.),
+                     Some(&filter),
+                     &mut |b, _, _: &b2::Vec2, _: &b2::Vec2, _| {
+                         hits.push(b);
+                         -1.
+                     });
+    assert_eq!(hits, vec![floor]);
+}
+
+fn cast_circle(world: &b2::World<NoUserData>,
+               from: b2::Vec2,
+               translation: b2::Vec2)
+               -> Vec<(b2::BodyHandle, b2::Vec2, f32)> {
+    let probe = b2::CircleShape::new_with(v(0., 0.), 0.5);
+    let xf = b2::Transform { pos: from, rot: b2::Rot::from_angle(0.) };
+    let mut hits = Vec::new();
+    world.shape_cast(&probe,
+                     &xf,
+                     translation,
+                     None,
+                     &mut |b, _, _: &b2::Vec2, normal: &b2::Vec2, fraction| {
+                         hits.push((b, *normal, fraction));
+                         fraction
+                     });
+    hits
+}
+
+#[test]
+fn shape_cast_hit_and_miss() {
+    let (world, floor) = world_with_floor();
+
+    let hits = cast_circle(&world, v(0., 2.), v(0., -4.));
+    assert_eq!(hits.len(), 1);
+    let (body, normal, fraction) = hits[0];
+    assert_eq!(body, floor);
+    // the circle touches the floor after moving down by 1.5
+    assert!((fraction - 0.375).abs() < 0.01);
+    assert!((normal.y - 1.).abs() < 1e-3);
+
+    // sideways above the floor, and short of it
+    assert!(cast_circle(&world, v(-2., 2.), v(4., 0.)).is_empty());
+    assert!(cast_circle(&world, v(0., 2.), v(0., -1.)).is_empty());
+}
+
+#[test]
+#[should_panic]
+fn shape_cast_borrowed_body() {
+    let (world, floor) = world_with_floor();
+    let _floor = world.body_mut(floor);
+    cast_circle(&world, v(0., 2.), v(0., -4.));
+}
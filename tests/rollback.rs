@@ -0,0 +1,47 @@
+extern crate wrapped2d;
+
+use wrapped2d::b2;
+use wrapped2d::user_data::NoUserData;
+
+fn step(world: &mut b2::World<NoUserData>) {
+    world.step(1. / 60., 8, 3);
+}
+
+#[test]
+fn rollback() {
+    let mut world = b2::World::<NoUserData>::new(&b2::Vec2 { x: 0., y: -10. });
+
+    let ground = world.create_body(&b2::BodyDef::new());
+    world.body_mut(ground).create_fast_fixture(&b2::PolygonShape::new_box(20., 1.), 0.);
+
+    let mut def = b2::BodyDef::new();
+    def.body_type = b2::BodyType::Dynamic;
+    let mut bodies = Vec::new();
+    for i in 0..6 {
+        def.position = b2::Vec2 { x: 0.1 * i as f32, y: 2. + 2.1 * i as f32 };
+        let body = world.create_body(&def);
+        world.body_mut(body).create_fast_fixture(&b2::PolygonShape::new_box(0.5, 0.5), 1.);
+        bodies.push(body);
+    }
+
+    for _ in 0..40 {
+        step(&mut world);
+    }
+
+    let state = world.save_state();
+    for _ in 0..90 {
+        step(&mut world);
+    }
+    let expected: Vec<_> = bodies.iter().map(|&b| world.body(b).transform().clone()).collect();
+
+    world.restore_state(&state).unwrap();
+    for _ in 0..90 {
+        step(&mut world);
+    }
+    for (&body, transform) in bodies.iter().zip(&expected) {
+        assert_eq!(world.body(body).transform(), transform);
+    }
+
+    world.destroy_body(bodies[0]);
+    assert!(world.restore_state(&state).is_err());
+}
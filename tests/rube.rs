@@ -0,0 +1,199 @@
+#![cfg(feature = "serialize")]
+
+extern crate serde_json;
+extern crate wrapped2d;
+
+use wrapped2d::b2;
+use wrapped2d::serialize::{BodyId, FixtureId, IdToHandle, JointId};
+use wrapped2d::serialize::rube::*;
+use wrapped2d::user_data::{UserData, UserDataTypes};
+
+const SCENE: &'static str = r#"{
+    "gravity": { "x": 0, "y": -10 },
+    "allowSleep": true,
+    "autoClearForces": true,
+    "continuousPhysics": true,
+    "subStepping": false,
+    "warmStarting": true,
+    "velocityIterations": 8,
+    "positionIterations": 3,
+    "stepsPerSecond": 60.0,
+    "body": [
+        {
+            "name": "ground",
+            "type": 0,
+            "position": 0,
+            "fixture": [
+                {
+                    "name": "floor",
+                    "friction": 0.6,
+                    "chain": {
+                        "vertices": { "x": [-20, 20], "y": [0, 0] }
+                    }
+                }
+            ]
+        },
+        {
+            "name": "wheel",
+            "type": 2,
+            "awake": true,
+            "position": { "x": 0, "y": 2 },
+            "fixture": [
+                {
+                    "density": 1,
+                    "filter-categoryBits": 2,
+                    "circle": { "center": 0, "radius": 0.5 },
+                    "customProperties": [ { "name": "weapon", "bool": true } ]
+                }
+            ],
+            "customProperties": [
+                { "name": "hp", "int": 3 },
+                { "name": "tint", "color": [255, 0, 0, 255] }
+            ]
+        },
+        {
+            "type": 2,
+            "position": { "x": 2, "y": 2 },
+            "fixture": [
+                {
+                    "density": 1,
+                    "polygon": {
+                        "vertices": { "x": [-0.5, 0.5, 0.5, -0.5], "y": [-0.5, -0.5, 0.5, 0.5] }
+                    }
+                }
+            ]
+        }
+    ],
+    "joint": [
+        {
+            "type": "gear",
+            "name": "gear",
+            "bodyA": 1,
+            "bodyB": 2,
+            "joint1": 1,
+            "joint2": 2,
+            "ratio": 2
+        },
+        {
+            "type": "revolute",
+            "bodyA": 0,
+            "bodyB": 1,
+            "anchorA": { "x": 0, "y": 2 },
+            "anchorB": 0,
+            "jointSpeed": 0
+        },
+        {
+            "type": "revolute",
+            "bodyA": 0,
+            "bodyB": 2,
+            "anchorA": { "x": 2, "y": 2 },
+            "anchorB": 0,
+            "enableMotor": true,
+            "maxMotorTorque": 10
+        }
+    ],
+    "image": [
+        { "name": "wheel", "file": "wheel.png", "body": 1, "scale": 1 },
+        { "name": "sky", "file": "sky.png", "body": -1 }
+    ]
+}"#;
+
+#[test]
+fn read_scene() {
+    let scene: RubeWorld = serde_json::from_str(SCENE).unwrap();
+    assert_eq!(scene.gravity, [0., -10.]);
+    assert_eq!(scene.bodies.len(), 3);
+    assert_eq!(scene.bodies[1].body_type, b2::BodyType::Dynamic);
+    assert_eq!(scene.bodies[1].custom_properties[0],
+               CustomProperty { name: "hp".to_owned(), value: PropertyValue::Int(3) });
+
+    let fixture = &scene.bodies[1].fixtures[0];
+    assert_eq!(fixture.category_bits, 2);
+    assert_eq!(fixture.mask_bits, 0xFFFF);
+    match fixture.shape {
+        RubeShape::Circle(ref circle) => assert_eq!(circle.radius, 0.5),
+        ref shape => panic!("unexpected shape {:?}", shape),
+    }
+    match scene.joints[0].kind {
+        RubeJointKind::Gear(ref gear) => assert_eq!((gear.joint1, gear.joint2), (1, 2)),
+        ref kind => panic!("unexpected joint {:?}", kind),
+    }
+
+    // zero vectors are written as 0
+    let written = serde_json::to_string(&scene).unwrap();
+    assert!(written.contains(r#""position":0,"#));
+    let again: RubeWorld = serde_json::from_str(&written).unwrap();
+    assert_eq!(again.images, scene.images);
+}
+
+#[test]
+fn read_hex_floats() {
+    let body: RubeBody = serde_json::from_str(r#"{
+        "position": { "x": "3F800000", "y": "C0000000" },
+        "angle": "40490FDB",
+        "massData-mass": "40000000",
+        "fixture": [
+            { "polygon": { "vertices": { "x": ["BF000000", 0.5, 0], "y": [0, 0, "3F000000"] } } }
+        ],
+        "customProperties": [ { "name": "speed", "float": "3FC00000" } ]
+    }"#).unwrap();
+    assert_eq!(body.position, [1., -2.]);
+    assert_eq!(body.angle, std::f32::consts::PI);
+    assert_eq!(body.mass, Some(2.));
+    match body.fixtures[0].shape {
+        RubeShape::Polygon(ref polygon) => {
+            assert_eq!(polygon.vertices[0], b2::Vec2 { x: -0.5, y: 0. });
+            assert_eq!(polygon.vertices[2], b2::Vec2 { x: 0., y: 0.5 });
+        }
+        ref shape => panic!("unexpected shape {:?}", shape),
+    }
+    assert_eq!(body.custom_properties[0].value, PropertyValue::Float(1.5));
+
+    assert!(serde_json::from_str::<RubeBody>(r#"{ "angle": "not hex" }"#).is_err());
+}
+
+struct Metadatas;
+
+impl UserDataTypes for Metadatas {
+    type BodyData = Metadata;
+    type JointData = Metadata;
+    type FixtureData = Metadata;
+}
+
+#[test]
+fn rebuild_scene() {
+    let scene: RubeWorld = serde_json::from_str(SCENE).unwrap();
+    let mut id_to_handle = IdToHandle::new();
+    let world: b2::World<Metadatas> = scene.rebuild(&mut id_to_handle);
+    assert_eq!(world.body_count(), 3);
+    assert_eq!(world.joint_count(), 3);
+
+    let wheel = id_to_handle.body_handle(BodyId(1)).unwrap();
+    assert_eq!(world.body(wheel).user_data().name, "wheel");
+    assert_eq!(world.body(wheel).user_data().images[0].file, "wheel.png");
+    let fixture = id_to_handle.fixture_handle(FixtureId(BodyId(1), 0)).unwrap();
+    assert_eq!(world.body(wheel).fixture(fixture).user_data().custom_properties[0].name,
+               "weapon");
+    let gear = id_to_handle.joint_handle(JointId(0)).unwrap();
+    assert_eq!(world.joint(gear).user_data().name, "gear");
+
+    let taken = RubeWorld::take(&world);
+    assert_eq!(taken.bodies.len(), 3);
+    assert_eq!(taken.images.len(), 1);
+
+    let mut written = scene.clone();
+    written.custom_properties.push(CustomProperty {
+        name: "level".to_owned(),
+        value: PropertyValue::Int(2),
+    });
+    written.update(&world);
+    assert_eq!(written.bodies.len(), 3);
+    assert_eq!(written.joints.len(), 3);
+    assert_eq!(written.images.len(), 2);
+    assert_eq!(written.images[0].file, "sky.png");
+    assert_eq!(written.images[1].body, 1);
+    assert_eq!(written.custom_properties.len(), 1);
+    let copy: b2::World<Metadatas> = written.rebuild(&mut IdToHandle::new());
+    assert_eq!(copy.body_count(), 3);
+    assert_eq!(copy.joint_count(), 3);
+}
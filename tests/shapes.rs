@@ -0,0 +1,162 @@
+extern crate wrapped2d;
+
+use std::convert::TryFrom;
+use wrapped2d::b2;
+
+fn v(x: f32, y: f32) -> b2::Vec2 {
+    b2::Vec2 { x: x, y: y }
+}
+
+#[test]
+fn scale_polygon() {
+    let polygon = b2::PolygonShape::new_box(1., 2.);
+    let scaled = polygon.scaled(2.).unwrap();
+    for i in 0..polygon.vertex_count() {
+        assert_eq!(*scaled.vertex(i), *polygon.vertex(i) * 2.);
+    }
+    assert_eq!(polygon.scaled(0.).err(), Some(b2::ShapeError::DegenerateHull));
+}
+
+#[test]
+fn scale_circle_and_edge() {
+    let circle = b2::CircleShape::new_with(v(1., 0.), 0.5);
+    let scaled = circle.scaled(-2.).unwrap();
+    assert_eq!(scaled.position(), v(-2., 0.));
+    assert_eq!(scaled.radius(), 1.);
+
+    let edge = b2::EdgeShape::new_with(&v(0., 0.), &v(1., 0.));
+    assert_eq!(edge.scaled(3.).unwrap().v2(), v(3., 0.));
+    assert_eq!(edge.scaled(0.).err(), Some(b2::ShapeError::VerticesTooClose(1)));
+}
+
+#[test]
+fn scale_loop() {
+    let square = [v(0., 0.), v(1., 0.), v(1., 1.), v(0., 1.)];
+    let chain = b2::ChainShape::new_loop(&square);
+    assert!(chain.is_loop());
+
+    let scaled = chain.scaled(2.).unwrap();
+    assert!(scaled.is_loop());
+    assert_eq!(scaled.vertices().len(), 5);
+    assert_eq!(scaled.vertices()[2], v(2., 2.));
+    assert_eq!(scaled.prev_vertex(), Some(v(0., 2.)));
+    assert_eq!(chain.scaled(0.).err(), Some(b2::ShapeError::VerticesTooClose(1)));
+}
+
+#[test]
+fn scale_closed_chain() {
+    // ends at its first vertex without being a loop
+    let outline = [v(0., 0.), v(1., 0.), v(1., 1.), v(0., 0.)];
+    let chain = b2::ChainShape::new_chain(&outline);
+    assert!(!chain.is_loop());
+
+    let scaled = chain.scaled(2.).unwrap();
+    assert!(!scaled.is_loop());
+    assert_eq!(scaled.vertices().len(), 4);
+    assert_eq!(scaled.prev_vertex(), None);
+    assert_eq!(scaled.next_vertex(), None);
+}
+
+fn round_trip(shape: b2::UnknownShape) {
+    let desc = b2::ShapeDesc::try_from(&shape).unwrap();
+    let rebuilt = desc.try_to_shape().unwrap();
+    assert_eq!(b2::ShapeDesc::try_from(&rebuilt).unwrap(), desc);
+}
+
+#[test]
+fn shape_desc_round_trip() {
+    round_trip(b2::CircleShape::new_with(v(1., 2.), 0.5).into());
+
+    let mut edge = b2::EdgeShape::new_with(&v(0., 0.), &v(1., 0.));
+    edge.set_v0(Some(v(-1., 0.5)));
+    round_trip(edge.into());
+
+    round_trip(b2::PolygonShape::new_oriented_box(1., 2., &v(1., 1.), 0.5).into());
+
+    let square = [v(0., 0.), v(1., 0.), v(1., 1.), v(0., 1.)];
+    let chain = b2::ChainShape::new_loop(&square);
+    match b2::ShapeDesc::try_from(&b2::UnknownShape::Chain(chain)).unwrap() {
+        b2::ShapeDesc::Chain { ref vertices, is_loop, .. } => {
+            assert!(is_loop);
+            assert_eq!(vertices.len(), 4);
+        }
+        ref desc => panic!("unexpected shape {:?}", desc),
+    }
+    round_trip(b2::ChainShape::new_loop(&square).into());
+
+    let mut chain = b2::ChainShape::new_chain(&square);
+    chain.set_next_vertex(Some(v(-1., 1.)));
+    round_trip(chain.into());
+
+    assert_eq!(b2::ShapeDesc::try_from(&b2::UnknownShape::Unknown),
+               Err(b2::ShapeError::UnknownShape));
+}
+
+#[test]
+fn checked_constructors() {
+    let triangle = [v(0., 0.), v(1., 0.), v(0., 1.)];
+    assert_eq!(b2::PolygonShape::try_new_with(&triangle).unwrap().vertex_count(), 3);
+    assert_eq!(b2::PolygonShape::try_new_with(&[v(0., 0.), v(1., 1.), v(2., 2.)]).err(),
+               Some(b2::ShapeError::DegenerateHull));
+
+    let circle: Vec<_> = (0..32)
+        .map(|i| {
+            let angle = i as f32 * 2. * ::std::f32::consts::PI / 32.;
+            v(angle.cos(), angle.sin())
+        })
+        .collect();
+    let hull = b2::PolygonShape::try_new_hull(&circle).unwrap();
+    assert_eq!(hull.vertex_count(), b2::MAX_POLYGON_VERTICES as i32);
+    assert!(hull.validate());
+
+    assert!(b2::CircleShape::try_new(v(0., 0.), 0.).is_ok());
+    assert_eq!(b2::CircleShape::try_new(v(0., 0.), -1.).err(),
+               Some(b2::ShapeError::InvalidRadius(-1.)));
+    assert_eq!(b2::EdgeShape::try_new_with(&v(0., 0.), &v(0., 0.)).err(),
+               Some(b2::ShapeError::VerticesTooClose(1)));
+
+    assert!(b2::ChainShape::try_new_loop(&triangle).unwrap().is_loop());
+    assert_eq!(b2::ChainShape::try_new_loop(&triangle[..2]).err(),
+               Some(b2::ShapeError::TooFewVertices(2)));
+    assert_eq!(b2::ChainShape::try_new_chain(&[v(0., 0.), v(0., 0.), v(1., 0.)]).err(),
+               Some(b2::ShapeError::VerticesTooClose(1)));
+}
+
+#[test]
+fn fixture_groups_keep_their_mass() {
+    use wrapped2d::b2::ShapeGroup;
+    use wrapped2d::user_data::NoUserData;
+
+    let mut world = b2::World::<NoUserData>::new(&v(0., -10.));
+    let body = world.create_body(&b2::BodyDef {
+        body_type: b2::BodyType::Dynamic,
+        ..b2::BodyDef::new()
+    });
+    let def = b2::FixtureDef { density: 1., ..b2::FixtureDef::new() };
+    let mut left = b2::Capsule::new(1., 0.5);
+    left.center = v(-3., 0.);
+    let mut right = b2::Capsule::new(1., 0.5);
+    right.center = v(3., 0.);
+    let capsule = left.mass_data(1.);
+
+    let mut body = world.body_mut(body);
+    let group = body.create_fixture_group(&left, &def);
+    assert!((body.mass() - capsule.mass).abs() < 1e-3);
+    // the second group resets the mass data, the first correction stays
+    body.create_fixture_group(&right, &def);
+    assert!((body.mass() - 2. * capsule.mass).abs() < 1e-3);
+    assert!(body.local_center().x.abs() < 1e-4);
+    let inertia = body.mass_data().inertia;
+    assert!((inertia - 2. * capsule.inertia).abs() < 1e-2);
+
+    body.create_fast_fixture(&b2::CircleShape::new_with(v(0., 0.), 0.5), 0.);
+    body.reset_mass_data();
+    body.set_rotation_fixed(true);
+    body.set_rotation_fixed(false);
+    assert!((body.mass() - 2. * capsule.mass).abs() < 1e-3);
+    assert!((body.mass_data().inertia - inertia).abs() < 1e-2);
+
+    body.destroy_fixture_group(group);
+    assert!((body.mass() - capsule.mass).abs() < 1e-3);
+    assert!((body.local_center().x - 3.).abs() < 1e-4);
+}
@@ -0,0 +1,73 @@
+extern crate wrapped2d;
+
+use wrapped2d::b2;
+use wrapped2d::user_data::NoUserData;
+
+fn world_with_box() -> (b2::World<NoUserData>, b2::BodyHandle) {
+    let mut world = b2::World::<NoUserData>::new(&b2::Vec2 { x: 0., y: 0. });
+    let def = b2::BodyDef {
+        body_type: b2::BodyType::Dynamic,
+        ..b2::BodyDef::new()
+    };
+    let body = world.create_body(&def);
+    // a mass of 4
+    world.body_mut(body).create_fast_fixture(&b2::PolygonShape::new_box(1., 1.), 1.);
+    (world, body)
+}
+
+#[test]
+fn forces_act_on_every_substep() {
+    let (mut world, body) = world_with_box();
+    let mut stepper = b2::Stepper::new(0.5);
+
+    world.body_mut(body).apply_force_to_center(&b2::Vec2 { x: 4., y: 0. }, true);
+    assert_eq!(stepper.update(&mut world, 1.), 2);
+    assert!((world.body(body).linear_velocity().x - 1.).abs() < 1e-4);
+    assert!(world.is_auto_clearing_forces());
+    assert!(!world.is_recording_transforms());
+
+    // cleared once the update is done
+    assert_eq!(stepper.update(&mut world, 1.), 2);
+    assert!((world.body(body).linear_velocity().x - 1.).abs() < 1e-4);
+}
+
+#[test]
+fn interpolates_recorded_transforms() {
+    let (mut world, body) = world_with_box();
+    world.body_mut(body).set_linear_velocity(&b2::Vec2 { x: 1., y: 0. });
+    let mut stepper = b2::Stepper::new(0.5);
+    stepper.record_transforms = true;
+
+    assert_eq!(stepper.update(&mut world, 1.25), 2);
+    assert!(world.is_recording_transforms());
+    assert!((stepper.alpha() - 0.5).abs() < 1e-4);
+    // between the positions after the first and the second step
+    let xf = stepper.interpolated_transform(&world, body).unwrap();
+    assert!((xf.pos.x - 0.75).abs() < 1e-4);
+
+    // too far behind, only `max_substeps` steps are taken
+    stepper.max_substeps = 3;
+    assert_eq!(stepper.update(&mut world, 10.), 3);
+    assert!(stepper.alpha() < 1.);
+}
+
+#[test]
+#[should_panic]
+fn zero_time_step() {
+    b2::Stepper::new(0.);
+}
+
+#[test]
+#[should_panic]
+fn set_zero_time_step() {
+    b2::Stepper::new(0.5).set_time_step(0.);
+}
+
+#[test]
+fn negative_frame_time() {
+    let (mut world, _) = world_with_box();
+    let mut stepper = b2::Stepper::new(0.5);
+    assert_eq!(stepper.update(&mut world, 0.25), 0);
+    assert_eq!(stepper.update(&mut world, -1.), 0);
+    assert!((stepper.alpha() - 0.5).abs() < 1e-6);
+}